@@ -0,0 +1,78 @@
+// Rendering throughput with a full voice load, and the cost of looking up fret
+// pitches. Run with `cargo bench`.
+//
+// Each mode renders one frame per process call too: every call then mixes a block of
+// one frame, which is the voice-by-voice, sample-by-sample cost the block mixer
+// replaced, so the ratio between the two rows is what mixing whole blocks buys.
+
+use guitar_audio_synth::GuitarSynthesizer;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SAMPLE_RATE: f32 = 44100.0;
+const VOICES: usize = 64;
+const BUFFER_SIZE: usize = 128;
+const PER_SAMPLE_ROUNDS: usize = 4;
+const ROUNDS: usize = 20;
+const LOOKUPS: usize = 1_000_000;
+
+// One second of audio with VOICES free voices sounding throughout
+fn render_second(synth: &mut GuitarSynthesizer, buffer: &mut [f32]) {
+    synth.stop_all();
+    for voice in 0..VOICES {
+        synth.play_note_by_name(["C", "E", "G", "B"][voice % 4], 2 + (voice / 4 % 4) as i32);
+    }
+    for _ in 0..SAMPLE_RATE as usize / buffer.len() {
+        synth.process(buffer);
+    }
+}
+
+// Milliseconds to render a second of audio in buffer_size chunks
+fn time_render(mode: u32, buffer_size: usize, rounds: usize) -> f64 {
+    let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+    synth.set_synthesis_mode(mode);
+    let mut buffer = vec![0.0; buffer_size];
+    render_second(&mut synth, &mut buffer);
+    
+    let start = Instant::now();
+    for _ in 0..rounds {
+        render_second(&mut synth, &mut buffer);
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / rounds as f64
+}
+
+fn bench_mode(name: &str, mode: u32) {
+    let block = time_render(mode, BUFFER_SIZE, ROUNDS);
+    let per_sample = time_render(mode, 1, PER_SAMPLE_ROUNDS);
+    println!(
+        "{name:<15} {VOICES} voices: {block:>8.2} ms per second of audio ({:.1}x real time), \
+         {per_sample:>8.2} ms one frame at a time ({:.1}x faster in blocks)",
+        1000.0 / block,
+        per_sample / block
+    );
+}
+
+// Every fret of every string, LOOKUPS times in all: once through the synthesizer's
+// cached table, and once with the powf per lookup that a note trigger used to pay
+fn bench_frequency_lookups() {
+    let synth = GuitarSynthesizer::new(SAMPLE_RATE);
+    let fingerings: Vec<(usize, i32)> = (0..synth.string_count()).flat_map(|s| (0..=24).map(move |f| (s, f))).collect();
+    let open: Vec<f32> = (0..synth.string_count()).map(|s| synth.open_frequency(s)).collect();
+    
+    let time = |lookup: &dyn Fn(usize, i32) -> f32| {
+        let start = Instant::now();
+        for &(string_index, fret) in fingerings.iter().cycle().take(LOOKUPS) {
+            black_box(lookup(black_box(string_index), black_box(fret)));
+        }
+        start.elapsed().as_secs_f64() * 1e9 / LOOKUPS as f64
+    };
+    let cached = time(&|string_index, fret| synth.get_frequency(string_index, fret));
+    let powf = time(&|string_index, fret| open[string_index] * 2.0_f32.powf(fret as f32 / 12.0));
+    println!("fret frequency  cached: {cached:>6.2} ns per lookup, powf: {powf:>6.2} ns per lookup");
+}
+
+fn main() {
+    bench_mode("karplus-strong", 0);
+    bench_mode("additive", 1);
+    bench_frequency_lookups();
+}
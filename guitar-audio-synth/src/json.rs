@@ -0,0 +1,198 @@
+// Minimal JSON support for settings presets and debug output, to avoid pulling serde
+// into the wasm build. Strings and booleans are checked for syntax but their values
+// aren't kept, since no setting reads them yet.
+
+pub(crate) enum JsonValue {
+    Null,
+    Bool,
+    Number(f64),
+    String,
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+    
+    pub(crate) fn as_f32(&self) -> Option<f32> {
+        match self {
+            JsonValue::Number(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+    
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+// JSON has no NaN or infinity, so those are written as null
+pub(crate) fn number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+// Deepest nesting of arrays and objects parse accepts, so hostile input can't overflow
+// the stack (wasm has no way to recover from that)
+const MAX_DEPTH: usize = 64;
+
+// None if the text isn't a single well-formed JSON value, or nests deeper than MAX_DEPTH
+pub(crate) fn parse(text: &str) -> Option<JsonValue> {
+    let mut parser = Parser { bytes: text.as_bytes(), position: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position == parser.bytes.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    // Arrays and objects currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+    
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+    
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+    
+    fn literal(&mut self, word: &str, value: JsonValue) -> Option<JsonValue> {
+        if self.bytes[self.position..].starts_with(word.as_bytes()) {
+            self.position += word.len();
+            Some(value)
+        } else {
+            None
+        }
+    }
+    
+    fn value(&mut self) -> Option<JsonValue> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.nested(Self::object),
+            b'[' => self.nested(Self::array),
+            b'"' => self.string().map(|_| JsonValue::String),
+            b't' => self.literal("true", JsonValue::Bool),
+            b'f' => self.literal("false", JsonValue::Bool),
+            b'n' => self.literal("null", JsonValue::Null),
+            _ => self.number(),
+        }
+    }
+    
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<JsonValue>) -> Option<JsonValue> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+    
+    fn object(&mut self) -> Option<JsonValue> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        if self.expect(b'}').is_some() {
+            return Some(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            let value = self.value()?;
+            fields.push((key, value));
+            if self.expect(b',').is_none() {
+                self.expect(b'}')?;
+                return Some(JsonValue::Object(fields));
+            }
+        }
+    }
+    
+    fn array(&mut self) -> Option<JsonValue> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.expect(b']').is_some() {
+            return Some(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.expect(b',').is_none() {
+                self.expect(b']')?;
+                return Some(JsonValue::Array(items));
+            }
+        }
+    }
+    
+    // Unescaped bytes are copied as they are and the result decoded once, at the end
+    fn string(&mut self) -> Option<String> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.position += 1;
+        
+        let mut text = Vec::new();
+        loop {
+            let byte = self.peek()?;
+            self.position += 1;
+            match byte {
+                b'"' => return String::from_utf8(text).ok(),
+                b'\\' => {
+                    let escaped = self.peek()?;
+                    self.position += 1;
+                    match escaped {
+                        b'n' => text.push(b'\n'),
+                        b't' => text.push(b'\t'),
+                        b'r' => text.push(b'\r'),
+                        b'u' => {
+                            let hex = std::str::from_utf8(self.bytes.get(self.position..self.position + 4)?).ok()?;
+                            self.position += 4;
+                            let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?).unwrap_or('\u{FFFD}');
+                            text.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        other => text.push(other),
+                    }
+                }
+                other => text.push(other),
+            }
+        }
+    }
+    
+    fn number(&mut self) -> Option<JsonValue> {
+        let start = self.position;
+        while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.position += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).ok()?;
+        text.parse().ok().map(JsonValue::Number)
+    }
+}
@@ -1,6 +1,11 @@
 use wasm_bindgen::prelude::*;
+use std::collections::VecDeque;
 use std::f32::consts::PI;
 
+mod json;
+
+use json::JsonValue;
+
 // Guitar string frequencies (standard tuning)
 const OPEN_STRING_FREQUENCIES: [f32; 6] = [
     82.41,  // E2
@@ -11,13 +16,973 @@ const OPEN_STRING_FREQUENCIES: [f32; 6] = [
     329.63, // E4
 ];
 
+// Standard 12-string tuning. Each course is two adjacent strings, octave string first
+// on the four lower courses and unison pairs on B and high E.
+const TWELVE_STRING_FREQUENCIES: [f32; 12] = [
+    164.81, 82.41,  // E3 / E2
+    220.00, 110.00, // A3 / A2
+    293.66, 146.83, // D4 / D3
+    392.00, 196.00, // G4 / G3
+    246.94, 246.94, // B3 / B3
+    329.63, 329.63, // E4 / E4
+];
+
+// Default amplitudes of harmonics 2 and up, relative to the fundamental
+const DEFAULT_HARMONICS: [f32; 6] = [
+    0.5,  // 2nd harmonic
+    0.3,  // 3rd harmonic
+    0.2,  // 4th harmonic
+    0.15, // 5th harmonic
+    0.1,  // 6th harmonic
+    0.05, // 7th harmonic
+];
+
+// Attack, decay, sustain level and release a new synthesizer starts with: a near-instant
+// pluck held at full level, then a two-second release
+const DEFAULT_ENVELOPE: (f32, f32, f32, f32) = (0.001, 0.0, 1.0, 2.0);
+
+// Pluck strength, string damping and drive a new synthesizer starts with
+const DEFAULT_PLUCK_STRENGTH: f32 = 0.8;
+const DEFAULT_STRING_DAMPING: f32 = 0.995;
+const DEFAULT_DRIVE: f32 = 1.0;
+
+// Nylon's softer, fewer upper partials, for set_string_material
+const NYLON_HARMONICS: [f32; 4] = [0.35, 0.15, 0.06, 0.02];
+
+// Partials beyond this many are ignored by set_harmonic_profile
+const MAX_HARMONICS: usize = 64;
+
+// Additive partials at or above Nyquist would alias and are skipped; over the top
+// ANTI_ALIAS_TAPER of the band below it they fade out, so a bend sweeping one across
+// Nyquist doesn't switch it off with a click
+const ANTI_ALIAS_TAPER: f32 = 0.1;
+
+// Palm-muted notes: short ring, extra loop damping
+const PALM_MUTE_RELEASE: f32 = 0.15;
+const PALM_MUTE_DURATION: f32 = 0.25;
+const PALM_MUTE_DAMPING: f32 = 0.97;
+
+// How far off a fret's pitch fret_position_for_frequency still accepts a frequency
+const FRET_POSITION_TOLERANCE_CENTS: f32 = 30.0;
+
+// Open strings only resonate with close harmonic matches, and only quietly
+const SYMPATHETIC_TOLERANCE_CENTS: f32 = 10.0;
+const SYMPATHETIC_MAX_HARMONIC: f32 = 8.0;
+const SYMPATHETIC_LEVEL: f32 = 0.15;
+
+// The octave doubler's companion voice is this many cents sharp of the octave and
+// starts this many seconds late, like the thinner string of a 12-string course
+const OCTAVE_DOUBLER_DETUNE_CENTS: f32 = 4.0;
+const OCTAVE_DOUBLER_DELAY: f32 = 0.008;
+
+// Unison stacks at most this many layers per note, spread up to this many cents
+const MAX_UNISON_VOICES: u32 = 8;
+const MAX_UNISON_DETUNE_CENTS: f32 = 50.0;
+
+// Metronome click: a short decaying sine burst, higher pitched on the downbeat
+const CLICK_DURATION: f32 = 0.03;
+const CLICK_DECAY: f32 = 0.006;
+const CLICK_FREQUENCY: f32 = 1500.0;
+const ACCENT_CLICK_FREQUENCY: f32 = 2500.0;
+
+// Guitar body modes as (frequency Hz, Q, gain): the Helmholtz air resonance, the main
+// top-plate mode and a couple of higher plate modes
+const BODY_MODES: [(f32, f32, f32); 4] = [
+    (100.0, 8.0, 0.6),
+    (200.0, 12.0, 0.45),
+    (400.0, 10.0, 0.3),
+    (2500.0, 3.0, 0.1),
+];
+
+// Time constant with which the pitch wheel glides to a new position, and its range
+const PITCH_BEND_SMOOTHING: f32 = 0.005;
+const MAX_PITCH_BEND: f32 = 24.0;
+
+// Note pressure (aftertouch) at 1.0 raises a note's sustain level by PRESSURE_LEVEL
+// (relative, so the decay lands higher and the release starts higher) and adds
+// PRESSURE_BRIGHTNESS of its first difference, a gentle treble tilt. Pressure changes
+// glide with time constant PRESSURE_SMOOTHING (seconds) so controllers don't zipper.
+const PRESSURE_LEVEL: f32 = 0.5;
+const PRESSURE_BRIGHTNESS: f32 = 2.0;
+const PRESSURE_SMOOTHING: f32 = 0.01;
+
+// String loops reserve room to grow this many times longer (the full pitch wheel
+// range down), so retuning in the audio callback doesn't allocate
+const LOOP_HEADROOM: usize = 4;
+
+// Chorus delay sweeps from CHORUS_BASE_DELAY up to that plus the depth (seconds)
+const CHORUS_BASE_DELAY: f32 = 0.007;
+const CHORUS_MAX_DEPTH: f32 = 0.01;
+
+// Three-band EQ: shelf corners and the mid peak's center and Q
+const EQ_LOW_FREQUENCY: f32 = 250.0;
+const EQ_MID_FREQUENCY: f32 = 1000.0;
+const EQ_MID_Q: f32 = 0.7;
+const EQ_HIGH_FREQUENCY: f32 = 4000.0;
+
+// Freeverb's comb and allpass lengths in samples at 44.1 kHz (a subset of its eight
+// combs), scaled to the sample rate. The right channel's are REVERB_STEREO_SPREAD longer.
+const REVERB_COMB_TUNING: [usize; 4] = [1116, 1277, 1422, 1557];
+const REVERB_ALLPASS_TUNING: [usize; 2] = [556, 341];
+const REVERB_STEREO_SPREAD: usize = 23;
+// Scales the reverb input so a fully wet tail is about as loud as the dry signal
+const REVERB_INPUT_GAIN: f32 = 0.1;
+
+// Longest loop the looper records; its buffers are reserved up front so the first
+// pass can grow without allocating in process
+const MAX_LOOP_SECONDS: f32 = 30.0;
+
+// Envelope wah level follower: attack and release in seconds, quick enough to catch a
+// pluck and slow enough that the sweep falls back with the note instead of chattering
+const ENVELOPE_WAH_ATTACK: f32 = 0.005;
+const ENVELOPE_WAH_RELEASE: f32 = 0.15;
+
+// Time constant with which auto gain recovers as voices end, so the level doesn't step
+const AUTO_GAIN_SMOOTHING: f32 = 0.02;
+
+// Once the output drops below the noise gate threshold the gate stays open this long
+// before its release starts, so zero crossings of low notes don't chatter it
+const NOISE_GATE_HOLD: f32 = 0.02;
+
+// Recent output kept for spectrum, which bounds the largest FFT it can run
+const SPECTRUM_HISTORY: usize = 8192;
+
+// detect_pitch looks for fundamentals in this range (a drop-A low string up to past
+// the 24th fret of the high E), in output no quieter than PITCH_MIN_RMS. YIN_THRESHOLD
+// is how far the normalized difference must dip to count as periodic.
+const PITCH_MIN_FREQUENCY: f32 = 50.0;
+const PITCH_MAX_FREQUENCY: f32 = 2000.0;
+const PITCH_MIN_RMS: f32 = 0.001;
+const YIN_THRESHOLD: f32 = 0.15;
+
+// With smooth retrigger, a replaced voice fades out over this long instead of stopping,
+// until set_retrigger_choke_ms picks another length
+const RETRIGGER_FADE: f32 = 0.01;
+
+// mute_strings damps each string over this long, quick enough to sound like a palm
+// landing and long enough not to click
+const STRING_MUTE_FADE: f32 = 0.015;
+
+// process renders in blocks of at most this many frames
+const MIX_BLOCK: usize = 128;
+
+// Corner frequency of the output DC-blocking high-pass
+const DC_BLOCK_CUTOFF: f32 = 20.0;
+
+// Recursive filter state below this is flushed to zero, long before it could decay into
+// the denormal range, where some CPUs slow down by orders of magnitude (about -400 dB)
+const DENORMAL_THRESHOLD: f32 = 1e-20;
+
+// is_silent treats output and effect tails below this level (about -100 dB) as silence
+const SILENCE_THRESHOLD: f32 = 1e-5;
+
+// Sample rate a synthesizer is built with when given one that isn't a positive number
+const FALLBACK_SAMPLE_RATE: f32 = 44100.0;
+
+// Noise seed used until set_seed is called, so renders are reproducible by default
+const DEFAULT_SEED: u64 = 0x5EED_CAFE;
+
+// Legato fret changes nudge the level of the ringing string
+const HAMMER_ON_GAIN: f32 = 1.1;
+const PULL_OFF_GAIN: f32 = 0.9;
+
+// Fret buzz on hard plucks: for FRET_BUZZ_DURATION seconds after onset the string
+// slaps the frets, clipping its positive swing at FRET_BUZZ_CLIP and rattling with
+// filtered noise, both fading out over the burst
+const FRET_BUZZ_VELOCITY: f32 = 0.8;
+const FRET_BUZZ_DURATION: f32 = 0.03;
+const FRET_BUZZ_CLIP: f32 = 0.4;
+const FRET_BUZZ_NOISE: f32 = 0.3;
+
+// Length of the pick-click noise burst at note onset
+const PICK_NOISE_DURATION: f32 = 0.002;
+
+// Level of the click (as a pick-noise amount) each fret crossing makes during a slide
+const SLIDE_FRET_NOISE: f32 = 0.05;
+
+// A schedule_note call waiting for its start time
+#[derive(Clone)]
+struct ScheduledNote {
+    string_index: usize,
+    fret: i32,
+    start_time: f32,
+    duration: f32,
+}
+
+// Feedback comb with a one-pole lowpass in the loop, so the reverb tail darkens. The
+// wide fields hold the loop instead while set_high_precision is on (see widen_line).
+#[derive(Clone)]
+struct ReverbComb {
+    buffer: Vec<f32>,
+    position: usize,
+    lowpass_state: f32,
+    wide_buffer: Vec<f64>,
+    wide_lowpass_state: f64,
+}
+
+impl ReverbComb {
+    fn new(length: usize) -> Self {
+        ReverbComb {
+            buffer: vec![0.0; length.max(1)],
+            position: 0,
+            lowpass_state: 0.0,
+            wide_buffer: Vec::new(),
+            wide_lowpass_state: 0.0,
+        }
+    }
+    
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.position];
+        self.lowpass_state = flush_denormal(output * (1.0 - damping) + self.lowpass_state * damping);
+        self.buffer[self.position] = flush_denormal(input + self.lowpass_state * feedback);
+        self.position = (self.position + 1) % self.buffer.len();
+        output
+    }
+    
+    fn process_wide(&mut self, input: f64, feedback: f64, damping: f64) -> f64 {
+        let output = self.wide_buffer[self.position];
+        self.wide_lowpass_state = flush_denormal_wide(output * (1.0 - damping) + self.wide_lowpass_state * damping);
+        self.wide_buffer[self.position] = flush_denormal_wide(input + self.wide_lowpass_state * feedback);
+        self.position = (self.position + 1) % self.wide_buffer.len();
+        output
+    }
+    
+    fn set_wide(&mut self, enabled: bool) {
+        if enabled && self.wide_buffer.len() != self.buffer.len() {
+            self.wide_lowpass_state = f64::from(self.lowpass_state);
+        } else if !enabled && !self.wide_buffer.is_empty() {
+            self.lowpass_state = self.wide_lowpass_state as f32;
+        }
+        widen_line(&mut self.buffer, &mut self.wide_buffer, enabled);
+    }
+}
+
+// Schroeder allpass that diffuses the comb echoes into a smooth tail
+#[derive(Clone)]
+struct ReverbAllpass {
+    buffer: Vec<f32>,
+    position: usize,
+    wide_buffer: Vec<f64>,
+}
+
+impl ReverbAllpass {
+    fn new(length: usize) -> Self {
+        ReverbAllpass { buffer: vec![0.0; length.max(1)], position: 0, wide_buffer: Vec::new() }
+    }
+    
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.position];
+        self.buffer[self.position] = flush_denormal(input + delayed * 0.5);
+        self.position = (self.position + 1) % self.buffer.len();
+        delayed - input
+    }
+    
+    fn process_wide(&mut self, input: f64) -> f64 {
+        let delayed = self.wide_buffer[self.position];
+        self.wide_buffer[self.position] = flush_denormal_wide(input + delayed * 0.5);
+        self.position = (self.position + 1) % self.wide_buffer.len();
+        delayed - input
+    }
+}
+
+// Oversampled soft clipping: the clipper runs at up to MAX_OVERSAMPLING times the sample
+// rate between two windowed-sinc lowpasses of OVERSAMPLING_TAPS taps per phase
+const MAX_OVERSAMPLING: u32 = 8;
+const OVERSAMPLING_TAPS: usize = 16;
+
+// One channel's soft clipper run at factor times the sample rate: the input is
+// interpolated up with a polyphase lowpass, clipped, and filtered back down, so the
+// harmonics the clipper adds above Nyquist are removed instead of aliasing
+#[derive(Clone)]
+struct ClipOversampler {
+    // Latest input samples, newest first
+    input: Vec<f32>,
+    // Clipped samples at the high rate, written twice so the latest kernel length of
+    // them, newest first, is always the contiguous run starting at position
+    output: Vec<f32>,
+    position: usize,
+}
+
+impl ClipOversampler {
+    fn new(kernel_length: usize) -> Self {
+        ClipOversampler { input: vec![0.0; OVERSAMPLING_TAPS], output: vec![0.0; 2 * kernel_length], position: 0 }
+    }
+    
+    fn process(&mut self, input: f32, kernel: &[f32], drive: f32) -> f32 {
+        let length = kernel.len();
+        let factor = length / OVERSAMPLING_TAPS;
+        self.input.copy_within(..OVERSAMPLING_TAPS - 1, 1);
+        self.input[0] = input;
+        for phase in 0..factor {
+            let interpolated: f32 = self.input.iter().enumerate().map(|(k, x)| kernel[k * factor + phase] * x).sum();
+            let clipped = saturate(factor as f32 * interpolated, drive);
+            self.position = (self.position + length - 1) % length;
+            self.output[self.position] = clipped;
+            self.output[self.position + length] = clipped;
+        }
+        let latest = &self.output[self.position..self.position + length];
+        flush_denormal(kernel.iter().zip(latest).map(|(h, z)| h * z).sum())
+    }
+}
+
+// Blackman-windowed sinc lowpass for oversampling by factor, cut off at the original
+// Nyquist frequency, with unity DC gain
+fn oversampling_kernel(factor: usize) -> Vec<f32> {
+    let length = factor * OVERSAMPLING_TAPS;
+    let center = (length - 1) as f32 / 2.0;
+    let cutoff = 0.5 / factor as f32;
+    let mut kernel: Vec<f32> = (0..length)
+        .map(|i| {
+            let t = i as f32 - center;
+            let sinc = if t == 0.0 { 1.0 } else { (2.0 * PI * cutoff * t).sin() / (2.0 * PI * cutoff * t) };
+            let window = 2.0 * PI * i as f32 / (length - 1) as f32;
+            sinc * (0.42 - 0.5 * window.cos() + 0.08 * (2.0 * window).cos())
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|h| *h /= sum);
+    kernel
+}
+
+// tanh saturation scaled to match the old cubic clipper's unity slope at zero and
+// its +-2/3 ceiling, but smooth everywhere instead of flattening out at +-1
+fn saturate(x: f32, drive: f32) -> f32 {
+    (2.0 / 3.0) * (1.5 * drive * x).tanh()
+}
+
+// Coefficient of the string-loop lowpass at set_brightness_decay(1.0)
+const MAX_LOOP_LOWPASS: f32 = 0.6;
+
+// Fret value in a chord shape for a string that is strummed but fretting-hand muted
+// (the "X" of a chord chart played percussively); -1 leaves the string out. Muted
+// strings give a short, dark thud.
+const MUTED_STRUM: i32 = -2;
+const MUTED_STRUM_DURATION: f32 = 0.05;
+const MUTED_STRUM_LEVEL: f32 = 0.6;
+
+// Attacks longer than this (swells) fade in over the pick instead of letting it click
+const SWELL_MIN_ATTACK: f32 = 0.01;
+
+// With velocity attack, the softest pluck's attack is this much longer than the
+// envelope's, still short of a swell so the pick is heard
+const VELOCITY_ATTACK_RANGE: f32 = 0.008;
+
+// How sharply the curved envelope ramps bend: an exponential release is down to
+// e^-ENVELOPE_CURVE_STEEPNESS (about -43 dB) before its final fade to zero
+const ENVELOPE_CURVE_STEEPNESS: f32 = 5.0;
+
+// Seconds a picked note rings, release included, unless the caller says otherwise
+const DEFAULT_NOTE_DURATION: f32 = 3.0;
+
+// Longest note a caller can ask for, release included, in seconds
+const MAX_NOTE_DURATION: f32 = 30.0;
+
+// Frets on the neck until set_fret_count is called
+const DEFAULT_FRET_COUNT: i32 = 24;
+
+// string_index used by voices that aren't bound to a physical string
+const FREE_VOICE: usize = usize::MAX;
+
+// Normalized RBJ band-pass biquad [b0, b1, b2, a1, a2] with 0 dB peak gain
+fn resonator_coefficients(sample_rate: f32, frequency: f32, q: f32) -> [f32; 5] {
+    let omega = 2.0 * PI * frequency.min(sample_rate * 0.45) / sample_rate;
+    let alpha = omega.sin() / (2.0 * q);
+    let a0 = 1.0 + alpha;
+    [alpha / a0, 0.0, -alpha / a0, -2.0 * omega.cos() / a0, (1.0 - alpha) / a0]
+}
+
+// RBJ shelving biquad [b0, b1, b2, a1, a2] with slope 1, boosting or cutting by gain_db
+// below (or, for a high shelf, above) frequency
+fn shelf_coefficients(sample_rate: f32, frequency: f32, gain_db: f32, high: bool) -> [f32; 5] {
+    let a = 10.0_f32.powf(gain_db / 40.0);
+    let omega = 2.0 * PI * frequency.min(sample_rate * 0.45) / sample_rate;
+    let cos = omega.cos();
+    let root_alpha = a.sqrt() * omega.sin() * std::f32::consts::SQRT_2;
+    let sign = if high { -1.0 } else { 1.0 };
+    let b0 = a * ((a + 1.0) - sign * (a - 1.0) * cos + root_alpha);
+    let b1 = sign * 2.0 * a * ((a - 1.0) - sign * (a + 1.0) * cos);
+    let b2 = a * ((a + 1.0) - sign * (a - 1.0) * cos - root_alpha);
+    let a0 = (a + 1.0) + sign * (a - 1.0) * cos + root_alpha;
+    let a1 = -sign * 2.0 * ((a - 1.0) + sign * (a + 1.0) * cos);
+    let a2 = (a + 1.0) + sign * (a - 1.0) * cos - root_alpha;
+    [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0]
+}
+
+// RBJ peaking biquad [b0, b1, b2, a1, a2], gain_db at frequency
+fn peaking_coefficients(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> [f32; 5] {
+    let a = 10.0_f32.powf(gain_db / 40.0);
+    let omega = 2.0 * PI * frequency.min(sample_rate * 0.45) / sample_rate;
+    let alpha = omega.sin() / (2.0 * q);
+    let a0 = 1.0 + alpha / a;
+    let b1 = -2.0 * omega.cos() / a0;
+    [(1.0 + alpha * a) / a0, b1, (1.0 - alpha * a) / a0, b1, (1.0 - alpha / a) / a0]
+}
+
+// play_tab reads each character column of a tab as this fraction of a beat (a 16th)
+const TAB_COLUMN_BEATS: f32 = 0.25;
+
+// The notes of six-line (or string_count-line) ASCII tablature as (string_index, fret,
+// column). Tab lines are those with a few dashes, after an optional short label such as
+// "e|"; the top line is the highest string. Stacked systems follow on from each other.
+// Bar lines take no time, and anything but digits (h, p, /, ~ and so on) is spacing.
+// Fret numbers too long to fit an i32 are skipped.
+fn parse_tab(tab: &str, string_count: usize) -> Vec<(usize, i32, usize)> {
+    let mut notes = Vec::new();
+    let mut system: Vec<&str> = Vec::new();
+    let mut start_column = 0;
+    for line in tab.lines().chain(std::iter::once("")) {
+        if let Some(content) = tab_line_content(line) {
+            system.push(content);
+            if system.len() < string_count {
+                continue;
+            }
+        }
+        if system.is_empty() {
+            continue;
+        }
+        
+        let mut width = 0;
+        for (row, content) in system.drain(..).enumerate() {
+            let string_index = string_count - 1 - row;
+            let mut column = start_column;
+            // The digits read so far and their column; None once they overflow
+            let mut fret: Option<(Option<i32>, usize)> = None;
+            for c in content.chars().filter(|&c| c != '|') {
+                match (c.to_digit(10), fret) {
+                    (Some(digit), Some((value, at))) => {
+                        let value = value.and_then(|v| v.checked_mul(10)?.checked_add(digit as i32));
+                        fret = Some((value, at));
+                    }
+                    (Some(digit), None) => fret = Some((Some(digit as i32), column)),
+                    (None, Some((value, at))) => {
+                        if let Some(value) = value {
+                            notes.push((string_index, value, at));
+                        }
+                        fret = None;
+                    }
+                    (None, None) => {}
+                }
+                column += 1;
+            }
+            if let Some((Some(value), at)) = fret {
+                notes.push((string_index, value, at));
+            }
+            width = width.max(column - start_column);
+        }
+        start_column += width;
+    }
+    notes
+}
+
+// The tab part of a line such as "e|--3--5--|", or None if it isn't a tab line
+fn tab_line_content(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let content = match line.find('|') {
+        Some(bar) if bar <= 3 => &line[bar + 1..],
+        _ => line.strip_prefix(|c: char| c.is_ascii_alphabetic()).filter(|rest| rest.starts_with('-')).unwrap_or(line),
+    };
+    (content.matches('-').count() >= 2).then_some(content)
+}
+
+// Pitch class (C = 0) of a note name such as "C", "F#", "Eb" or "Bbb"
+fn parse_note_name(name: &str) -> Option<i32> {
+    let mut chars = name.trim().chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    
+    let mut alteration = 0;
+    for accidental in chars {
+        match accidental {
+            '#' | '♯' => alteration += 1,
+            'b' | '♭' => alteration -= 1,
+            _ => return None,
+        }
+    }
+    Some(base + alteration)
+}
+
+// Linear-interpolation resampling of recorded audio to a new length
+fn resample_linear(samples: &[f32], length: usize) -> Vec<f32> {
+    if samples.is_empty() {
+        return vec![0.0; length];
+    }
+    let step = samples.len() as f32 / length.max(1) as f32;
+    (0..length)
+        .map(|k| {
+            let position = k as f32 * step;
+            let index = (position as usize).min(samples.len() - 1);
+            let fraction = position - index as f32;
+            let a = samples[index];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+            a + (b - a) * fraction
+        })
+        .collect()
+}
+
+// Fundamental of samples by YIN: the first lag where the cumulative mean normalized
+// difference dips below YIN_THRESHOLD, taken at the bottom of that dip and refined
+// with a parabola. Lags are capped at half the samples, which raises the lowest
+// detectable pitch at high sample rates. None when nothing is periodic enough.
+fn yin_pitch(samples: &[f32], sample_rate: f32) -> Option<f32> {
+    let min_lag = ((sample_rate / PITCH_MAX_FREQUENCY) as usize).max(2);
+    let max_lag = ((sample_rate / PITCH_MIN_FREQUENCY) as usize).min(samples.len() / 2);
+    if min_lag + 1 >= max_lag {
+        return None;
+    }
+    let window = samples.len() - max_lag;
+    let difference = |lag: usize| -> f32 {
+        samples[..window].iter().zip(&samples[lag..lag + window]).map(|(a, b)| (a - b) * (a - b)).sum()
+    };
+    
+    let mut normalized = vec![1.0; max_lag + 1];
+    let mut running_sum = 0.0;
+    for (lag, value) in normalized.iter_mut().enumerate().skip(1) {
+        let d = difference(lag);
+        running_sum += d;
+        if running_sum > 0.0 {
+            *value = d * lag as f32 / running_sum;
+        }
+    }
+    
+    let mut lag = (min_lag..max_lag).find(|&lag| normalized[lag] < YIN_THRESHOLD)?;
+    while lag + 1 < max_lag && normalized[lag + 1] < normalized[lag] {
+        lag += 1;
+    }
+    let (before, at, after) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+    let curvature = before - 2.0 * at + after;
+    let offset = if curvature > 0.0 { 0.5 * (before - after) / curvature } else { 0.0 };
+    Some(sample_rate / (lag as f32 + offset))
+}
+
+// In-place iterative radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    
+    let mut size = 2;
+    while size <= n {
+        let angle = -2.0 * PI / size as f32;
+        for start in (0..n).step_by(size) {
+            for k in 0..size / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + size / 2);
+                let twiddled_re = re[b] * cos - im[b] * sin;
+                let twiddled_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - twiddled_re;
+                im[b] = im[a] - twiddled_im;
+                re[a] += twiddled_re;
+                im[a] += twiddled_im;
+            }
+        }
+        size *= 2;
+    }
+}
+
+// splitmix64 scramble so that any seed, including 0, gives a usable xorshift state
+fn seed_noise_state(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)).max(1)
+}
+
+// xorshift64* white noise in [-1, 1)
+fn xorshift_noise(state: &mut u64) -> f32 {
+    let mut x = *state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    *state = x;
+    let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40;
+    (bits as f32 / (1u32 << 24) as f32) * 2.0 - 1.0
+}
+
+// Zero for anything too small to hear, so feedback paths decay to silence cleanly
+fn flush_denormal(x: f32) -> f32 {
+    if x.abs() < DENORMAL_THRESHOLD {
+        0.0
+    } else {
+        x
+    }
+}
+
+// flush_denormal for the f64 feedback paths of set_high_precision
+fn flush_denormal_wide(x: f64) -> f64 {
+    if x.abs() < f64::from(DENORMAL_THRESHOLD) {
+        0.0
+    } else {
+        x
+    }
+}
+
+// Moves a feedback line between its f32 storage and f64 storage for set_high_precision,
+// keeping what it holds. While wide, narrow is left zeroed; wide is rebuilt whenever its
+// length no longer matches narrow's, e.g. after narrow was resized.
+fn widen_line(narrow: &mut [f32], wide: &mut Vec<f64>, enabled: bool) {
+    if enabled {
+        if wide.len() != narrow.len() {
+            *wide = narrow.iter().map(|&sample| f64::from(sample)).collect();
+            narrow.fill(0.0);
+        }
+    } else if !wide.is_empty() {
+        narrow.iter_mut().zip(wide.iter()).for_each(|(sample, &wide)| *sample = wide as f32);
+        *wide = Vec::new();
+    }
+}
+
+// Standard tuning reference for A4, until set_concert_pitch picks another
+const DEFAULT_CONCERT_PITCH: f32 = 440.0;
+
+// MIDI note 69 is A4, sounding at concert_pitch
+fn midi_to_frequency(midi_note: f32, concert_pitch: f32) -> f32 {
+    concert_pitch * 2.0_f32.powf((midi_note - 69.0) / 12.0)
+}
+
+fn frequency_to_midi(frequency: f32, concert_pitch: f32) -> f32 {
+    69.0 + 12.0 * (frequency / concert_pitch).log2()
+}
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+// Chord qualities recognized by identify_chord: name suffix and intervals above the root
+const CHORD_QUALITIES: [(&str, &[i32]); 8] = [
+    ("", &[0, 4, 7]),
+    ("m", &[0, 3, 7]),
+    ("7", &[0, 4, 7, 10]),
+    ("m7", &[0, 3, 7, 10]),
+    ("maj7", &[0, 4, 7, 11]),
+    ("sus2", &[0, 2, 7]),
+    ("sus4", &[0, 5, 7]),
+    ("dim", &[0, 3, 6]),
+];
+
+// chord_voicing keeps shapes within this many frets (one per finger) and at least
+// VOICING_MIN_STRINGS strings
+const VOICING_SPAN: i32 = 4;
+const VOICING_MIN_STRINGS: usize = 3;
+
+// Nearest equal-tempered note in scientific pitch notation, e.g. 440.0 -> "A4" at a
+// concert pitch of 440
+fn frequency_to_note_name(frequency: f32, concert_pitch: f32) -> Option<String> {
+    if !frequency.is_finite() || frequency <= 0.0 {
+        return None;
+    }
+    let midi_note = frequency_to_midi(frequency, concert_pitch).round() as i32;
+    let octave = midi_note.div_euclid(12) - 1;
+    Some(format!("{}{}", NOTE_NAMES[midi_note.rem_euclid(12) as usize], octave))
+}
+
+// Harmonic that sounds when a string is touched over the given fret: the node of
+// partial n nearest each fret (e.g. 12 -> octave, 7 and 19 -> octave + fifth)
+fn harmonic_at_node(fret: i32) -> Option<f32> {
+    match fret {
+        12 => Some(2.0),
+        7 | 19 => Some(3.0),
+        5 | 24 => Some(4.0),
+        4 | 9 | 16 => Some(5.0),
+        3 => Some(6.0),
+        _ => None,
+    }
+}
+
+// 16-bit PCM mono RIFF/WAVE byte stream
+fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    const BYTES_PER_SAMPLE: u32 = 2;
+    let data_len = u32::try_from(samples.len() * BYTES_PER_SAMPLE as usize).unwrap_or(u32::MAX - 36);
+    
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    
+    // fmt chunk
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * BYTES_PER_SAMPLE).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&(BYTES_PER_SAMPLE as u16).to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    
+    // data chunk
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples.iter().take((data_len / BYTES_PER_SAMPLE) as usize) {
+        let pcm = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&pcm.to_le_bytes());
+    }
+    wav
+}
+
+// Pitch of every fret from the nut on each string, row by row: one row per open-string
+// frequency, with a column per intonation offset
+fn fret_frequency_table(open_frequencies: &[f32], intonation_offsets: &[f32]) -> Vec<f32> {
+    open_frequencies
+        .iter()
+        .flat_map(|&open| {
+            intonation_offsets
+                .iter()
+                .enumerate()
+                .map(move |(fret, &cents)| open * 2.0_f32.powf(fret as f32 / 12.0 + cents / 1200.0))
+        })
+        .collect()
+}
+
+// Delay-line length and tuning-allpass coefficient for a Karplus-Strong loop.
+// The two-point average in the feedback loop adds half a sample of delay and the
+// first-order allpass supplies the remaining fractional delay, so the loop period
+// matches sample_rate / frequency closely. A brightness-decay lowpass with
+// coefficient lowpass adds its own phase delay at the fundamental, which comes off
+// the line length.
+fn string_loop_tuning(sample_rate: f32, frequency: f32, lowpass: f32) -> (usize, f32) {
+    let mut period = sample_rate / frequency + 0.5;
+    if lowpass > 0.0 {
+        let omega = 2.0 * PI * frequency / sample_rate;
+        period -= (lowpass * omega.sin()).atan2(1.0 - lowpass * omega.cos()) / omega;
+    }
+    let length = ((period - 0.1).floor() as usize).max(2);
+    let fraction = period - length as f32;
+    (length, (1.0 - fraction) / (1.0 + fraction))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SynthesisMode {
+    KarplusStrong = 0,
+    Additive = 1,
+}
+
+impl SynthesisMode {
+    fn from_u32(mode: u32) -> Option<Self> {
+        match mode {
+            0 => Some(SynthesisMode::KarplusStrong),
+            1 => Some(SynthesisMode::Additive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StringMaterial {
+    Steel = 0,
+    Nylon = 1,
+}
+
+impl StringMaterial {
+    fn from_u32(material: u32) -> Option<Self> {
+        match material {
+            0 => Some(StringMaterial::Steel),
+            1 => Some(StringMaterial::Nylon),
+            _ => None,
+        }
+    }
+}
+
+// Shape of the attack and release ramps (see GuitarNote::ramp)
+#[derive(Clone, Copy, PartialEq)]
+enum EnvelopeCurve {
+    Linear = 0,
+    Exponential = 1,
+    Logarithmic = 2,
+}
+
+impl EnvelopeCurve {
+    fn from_u32(curve: u32) -> Option<Self> {
+        match curve {
+            0 => Some(EnvelopeCurve::Linear),
+            1 => Some(EnvelopeCurve::Exponential),
+            2 => Some(EnvelopeCurve::Logarithmic),
+            _ => None,
+        }
+    }
+}
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct GuitarSynthesizer {
     sample_rate: f32,
     current_time: f32,
     active_notes: Vec<GuitarNote>,
+    // Ordered by start time
+    scheduled_notes: VecDeque<ScheduledNote>,
     pluck_strength: f32,
     string_damping: f32,
+    string_damping_overrides: Vec<Option<f32>>,
+    synthesis_mode: SynthesisMode,
+    noise_state: u64,
+    open_string_frequencies: Vec<f32>,
+    // Tuning the synthesizer was built with (at the current concert pitch), restored by
+    // import_settings
+    default_tuning: Vec<f32>,
+    concert_pitch: f32,
+    capo: i32,
+    // Partial capo: its fret and the strings it covers, on top of the full capo
+    partial_capo: i32,
+    partial_capo_strings: Vec<bool>,
+    fret_count: i32,
+    // Cents off equal temperament, indexed by fret from the nut (capo not included)
+    intonation_offsets: Vec<f32>,
+    // fret_frequency_table of the tuning and intonation, so playing a note doesn't need
+    // a powf; rebuilt whenever either changes
+    fret_frequencies: Vec<f32>,
+    attack_time: f32,
+    decay_time: f32,
+    sustain_level: f32,
+    release_time: f32,
+    envelope_curve: EnvelopeCurve,
+    velocity_attack: bool,
+    tone_cutoff: f32,
+    tone_coefficient: f32,
+    // Per-channel effect state has two channels until process_multi asks for more
+    tone_state: Vec<f32>,
+    eq_enabled: bool,
+    eq_gains: [f32; 3],
+    // Low shelf, mid peak, high shelf
+    eq_coefficients: [[f32; 5]; 3],
+    eq_state: Vec<[[f32; 2]; 3]>,
+    vibrato_depth: f32,
+    vibrato_rate: f32,
+    vibrato_phase: f32,
+    pitch_bend: f32,
+    pitch_bend_target: f32,
+    // Whammy bar offset in semitones, gliding toward its target at whammy_rate per second
+    whammy: f32,
+    whammy_target: f32,
+    whammy_rate: f32,
+    tremolo_depth: f32,
+    tremolo_rate: f32,
+    tremolo_phase: f32,
+    string_pans: Vec<f32>,
+    string_muted: Vec<bool>,
+    // When any string is soloed, only soloed strings are heard
+    string_soloed: Vec<bool>,
+    dynamic_pan: f32,
+    // Output process_multi sends each string to, wrapped to the channel count
+    string_channels: Vec<usize>,
+    // process_multi's per-channel voice mix, kept to avoid allocating per call
+    channel_mixes: Vec<[f32; MIX_BLOCK]>,
+    delay_feedback: f32,
+    delay_mix: f32,
+    delay_buffers: Vec<Vec<f32>>,
+    // The delay lines at f64 while high_precision is on, otherwise empty
+    delay_wide_buffers: Vec<Vec<f64>>,
+    delay_position: usize,
+    chorus_rate: f32,
+    chorus_depth: f32,
+    chorus_mix: f32,
+    chorus_phase: f32,
+    chorus_buffers: Vec<Vec<f32>>,
+    chorus_position: usize,
+    reverb_feedback: f32,
+    reverb_damping: f32,
+    reverb_mix: f32,
+    reverb_width: f32,
+    reverb_combs: [Vec<ReverbComb>; 2],
+    reverb_allpasses: [Vec<ReverbAllpass>; 2],
+    // Delay and reverb feedback run in f64 (see set_high_precision)
+    high_precision: bool,
+    // Haas widening: the right channel's delay line (empty when off)
+    haas_buffer: Vec<f32>,
+    haas_position: usize,
+    // Ring buffer of the last SPECTRUM_HISTORY output frames (stereo averaged)
+    output_history: Vec<f32>,
+    history_position: usize,
+    // Looper: the first recording pass sets the loop length, later passes overdub
+    loop_buffers: [Vec<f32>; 2],
+    loop_length: usize,
+    loop_position: usize,
+    loop_recording: bool,
+    loop_playing: bool,
+    max_voices: usize,
+    smooth_retrigger: bool,
+    retrigger_fade: f32,
+    legato: bool,
+    sustain_pedal: bool,
+    wah_frequency: f32,
+    wah_resonance: f32,
+    wah_mix: f32,
+    auto_wah_rate: f32,
+    auto_wah_depth: f32,
+    auto_wah_phase: f32,
+    // Envelope wah: sensitivity (0 when off), resting center, sweep range in octaves,
+    // follower smoothing and the followed level per channel
+    envelope_wah_sensitivity: f32,
+    envelope_wah_base: f32,
+    envelope_wah_range: f32,
+    envelope_wah_attack: f32,
+    envelope_wah_release: f32,
+    envelope_wah_level: Vec<f32>,
+    // State-variable filter integrators per channel
+    wah_state: Vec<[f32; 2]>,
+    compressor_threshold: f32,
+    compressor_ratio: f32,
+    compressor_attack: f32,
+    compressor_release: f32,
+    compressor_envelope: Vec<f32>,
+    noise_gate_threshold: f32,
+    noise_gate_release: f32,
+    // Seconds the gate stays open before releasing, and its current gain
+    noise_gate_hold: f32,
+    noise_gate_gain: f32,
+    master_gain: f32,
+    oversampling: usize,
+    // Shared by every channel's oversampler (empty at factor 1)
+    oversampling_kernel: Vec<f32>,
+    clip_oversamplers: Vec<ClipOversampler>,
+    // Level auto gain currently applies to the voice mix (1.0 when off)
+    auto_gain: bool,
+    auto_gain_level: f32,
+    drive: f32,
+    pluck_position: f32,
+    brightness_decay: f32,
+    harmonic_profile: Vec<f32>,
+    sympathetic_resonance: f32,
+    octave_doubler: f32,
+    unison_voices: u32,
+    unison_detune: f32,
+    fret_buzz: f32,
+    pick_noise: f32,
+    metronome_enabled: bool,
+    metronome_bpm: f32,
+    metronome_beats_per_bar: u32,
+    metronome_volume: f32,
+    metronome_start: f32,
+    // Frets of the last play_chord, which the arpeggiator steps through
+    held_chord: Vec<i32>,
+    arpeggiator_enabled: bool,
+    arpeggiator_rate: f32,
+    arpeggiator_pattern: Vec<i32>,
+    arpeggiator_step: usize,
+    arpeggiator_next: f32,
+    last_buffer_peak: f32,
+    last_buffer_rms: f32,
+    dc_block_enabled: bool,
+    dc_block_input: Vec<f32>,
+    dc_block_output: Vec<f32>,
+    body_resonance_enabled: bool,
+    body_coefficients: [[f32; 5]; BODY_MODES.len()],
+    // Transposed direct form II state per channel and mode
+    body_state: Vec<[[f32; 2]; BODY_MODES.len()]>,
 }
 
 #[wasm_bindgen]
@@ -27,223 +992,4381 @@ pub struct GuitarNote {
     amplitude: f32,
     phase: f32,
     attack_time: f32,
+    decay_time: f32,
+    sustain_level: f32,
     release_time: f32,
+    // Level fade_out cut in at, which the release ramps down from instead of the
+    // attack/decay level at its start
+    fade_level: Option<f32>,
+    envelope_curve: EnvelopeCurve,
     start_time: f32,
     duration: f32,
     harmonics: Vec<f32>,
     string_index: usize,
+    velocity: f32,
+    palm_mute: bool,
+    flageolet: bool,
+    // MIDI key that started the note, for midi_note_off
+    midi_note: Option<u8>,
+    // Released while the sustain pedal was down; releases when it comes up
+    pending_release: bool,
+    // Seconds of fret buzz left, and the buzz noise filter state
+    buzz_remaining: f32,
+    buzz_filter: f32,
+    // Samples of pick or fret click left, and its level
+    click_remaining: usize,
+    click_level: f32,
+    // Slide in progress: frets still to cross, pitch ratio per fret, seconds per fret
+    // and time since the last crossing
+    slide_frets_left: u32,
+    slide_ratio: f32,
+    slide_fret_time: f32,
+    slide_clock: f32,
+    pressure: f32,
+    pressure_target: f32,
+    // Last unemphasized sample, for the pressure brightness tilt
+    pressure_previous: f32,
+    // Octave doubler voice riding along with the string's played note
+    companion: bool,
+    bend_target: f32,
+    bend_rate: f32,
+    mode: SynthesisMode,
+    // Karplus-Strong string state
+    delay_line: Vec<f32>,
+    // Spare buffer retune_string resamples into, swapped with delay_line
+    resample_buffer: Vec<f32>,
+    delay_index: usize,
+    loop_frequency: f32,
+    allpass_coefficient: f32,
+    allpass_input: f32,
+    allpass_output: f32,
+    // Brightness-decay lowpass in the loop (0 = off) and its state
+    loop_lowpass: f32,
+    loop_lowpass_state: f32,
 }
 
 #[wasm_bindgen]
 impl GuitarSynthesizer {
     #[wasm_bindgen(constructor)]
     pub fn new(sample_rate: f32) -> Self {
+        Self::new_with_strings(sample_rate, &OPEN_STRING_FREQUENCIES)
+    }
+    
+    // Any number of strings, tuned low to high by open_frequencies (7-string, bass, ...).
+    // Falls back to standard six-string tuning if the list is empty or not all positive,
+    // and to FALLBACK_SAMPLE_RATE if the sample rate isn't a positive number.
+    pub fn new_with_strings(sample_rate: f32, open_frequencies: &[f32]) -> Self {
+        let sample_rate = if sample_rate.is_finite() && sample_rate > 0.0 {
+            sample_rate
+        } else {
+            FALLBACK_SAMPLE_RATE
+        };
+        let valid = !open_frequencies.is_empty() && open_frequencies.iter().all(|f| f.is_finite() && *f > 0.0);
+        let open_frequencies = if valid { open_frequencies } else { &OPEN_STRING_FREQUENCIES };
+        
         GuitarSynthesizer {
             sample_rate,
             current_time: 0.0,
             active_notes: Vec::new(),
-            pluck_strength: 0.8,
-            string_damping: 0.995,
+            scheduled_notes: VecDeque::new(),
+            pluck_strength: DEFAULT_PLUCK_STRENGTH,
+            string_damping: DEFAULT_STRING_DAMPING,
+            string_damping_overrides: vec![None; open_frequencies.len()],
+            synthesis_mode: SynthesisMode::KarplusStrong,
+            noise_state: seed_noise_state(DEFAULT_SEED),
+            open_string_frequencies: open_frequencies.to_vec(),
+            default_tuning: open_frequencies.to_vec(),
+            concert_pitch: DEFAULT_CONCERT_PITCH,
+            capo: 0,
+            partial_capo: 0,
+            partial_capo_strings: vec![false; open_frequencies.len()],
+            fret_count: DEFAULT_FRET_COUNT,
+            intonation_offsets: vec![0.0; DEFAULT_FRET_COUNT as usize + 1],
+            fret_frequencies: fret_frequency_table(open_frequencies, &[0.0; DEFAULT_FRET_COUNT as usize + 1]),
+            attack_time: DEFAULT_ENVELOPE.0,
+            decay_time: DEFAULT_ENVELOPE.1,
+            sustain_level: DEFAULT_ENVELOPE.2,
+            release_time: DEFAULT_ENVELOPE.3,
+            envelope_curve: EnvelopeCurve::Linear,
+            velocity_attack: false,
+            tone_cutoff: sample_rate / 2.0,
+            tone_coefficient: 1.0,
+            tone_state: vec![0.0; 2],
+            eq_enabled: false,
+            eq_gains: [0.0; 3],
+            eq_coefficients: [[1.0, 0.0, 0.0, 0.0, 0.0]; 3],
+            eq_state: vec![[[0.0; 2]; 3]; 2],
+            vibrato_depth: 0.0,
+            vibrato_rate: 0.0,
+            vibrato_phase: 0.0,
+            pitch_bend: 0.0,
+            pitch_bend_target: 0.0,
+            whammy: 0.0,
+            whammy_target: 0.0,
+            whammy_rate: 0.0,
+            tremolo_depth: 0.0,
+            tremolo_rate: 0.0,
+            tremolo_phase: 0.0,
+            string_pans: vec![0.0; open_frequencies.len()],
+            string_muted: vec![false; open_frequencies.len()],
+            string_soloed: vec![false; open_frequencies.len()],
+            dynamic_pan: 0.0,
+            string_channels: (0..open_frequencies.len()).collect(),
+            channel_mixes: Vec::new(),
+            delay_feedback: 0.0,
+            delay_mix: 0.0,
+            delay_buffers: vec![Vec::new(); 2],
+            delay_wide_buffers: vec![Vec::new(); 2],
+            delay_position: 0,
+            chorus_rate: 0.0,
+            chorus_depth: 0.0,
+            chorus_mix: 0.0,
+            chorus_phase: 0.0,
+            chorus_buffers: vec![Vec::new(); 2],
+            reverb_feedback: 0.84,
+            reverb_damping: 0.2,
+            reverb_mix: 0.0,
+            reverb_width: 1.0,
+            reverb_combs: [Vec::new(), Vec::new()],
+            reverb_allpasses: [Vec::new(), Vec::new()],
+            high_precision: false,
+            haas_buffer: Vec::new(),
+            output_history: vec![0.0; SPECTRUM_HISTORY],
+            history_position: 0,
+            haas_position: 0,
+            loop_buffers: [Vec::new(), Vec::new()],
+            loop_length: 0,
+            loop_position: 0,
+            loop_recording: false,
+            loop_playing: false,
+            chorus_position: 0,
+            max_voices: usize::MAX,
+            smooth_retrigger: false,
+            retrigger_fade: RETRIGGER_FADE,
+            legato: false,
+            sustain_pedal: false,
+            wah_frequency: 800.0,
+            wah_resonance: 4.0,
+            wah_mix: 0.0,
+            auto_wah_rate: 0.0,
+            auto_wah_depth: 0.0,
+            auto_wah_phase: 0.0,
+            envelope_wah_sensitivity: 0.0,
+            envelope_wah_base: 400.0,
+            envelope_wah_range: 2.0,
+            envelope_wah_attack: 0.0,
+            envelope_wah_release: 0.0,
+            envelope_wah_level: vec![0.0; 2],
+            wah_state: vec![[0.0; 2]; 2],
+            compressor_threshold: 1.0,
+            compressor_ratio: 1.0,
+            compressor_attack: 0.0,
+            compressor_release: 0.0,
+            compressor_envelope: vec![0.0; 2],
+            noise_gate_threshold: 0.0,
+            noise_gate_release: 0.05,
+            noise_gate_hold: 0.0,
+            noise_gate_gain: 1.0,
+            master_gain: 0.3,
+            oversampling: 1,
+            oversampling_kernel: Vec::new(),
+            clip_oversamplers: vec![ClipOversampler::new(0); 2],
+            auto_gain: false,
+            auto_gain_level: 1.0,
+            drive: DEFAULT_DRIVE,
+            pluck_position: 0.0,
+            brightness_decay: 0.0,
+            harmonic_profile: DEFAULT_HARMONICS.to_vec(),
+            sympathetic_resonance: 0.0,
+            octave_doubler: 0.0,
+            unison_voices: 1,
+            unison_detune: 0.0,
+            fret_buzz: 0.0,
+            pick_noise: 0.0,
+            metronome_enabled: false,
+            metronome_bpm: 120.0,
+            metronome_beats_per_bar: 4,
+            metronome_volume: 0.5,
+            metronome_start: 0.0,
+            held_chord: Vec::new(),
+            arpeggiator_enabled: false,
+            arpeggiator_rate: 8.0,
+            arpeggiator_pattern: Vec::new(),
+            arpeggiator_step: 0,
+            arpeggiator_next: 0.0,
+            last_buffer_peak: 0.0,
+            last_buffer_rms: 0.0,
+            dc_block_enabled: true,
+            dc_block_input: vec![0.0; 2],
+            dc_block_output: vec![0.0; 2],
+            body_resonance_enabled: false,
+            body_coefficients: BODY_MODES.map(|(frequency, q, _)| resonator_coefficients(sample_rate, frequency, q)),
+            body_state: vec![[[0.0; 2]; BODY_MODES.len()]; 2],
         }
     }
     
+    // 12 strings in paired courses (see TWELVE_STRING_FREQUENCIES); chord shapes list
+    // each course's fret twice
+    pub fn new_twelve_string(sample_rate: f32) -> Self {
+        Self::new_with_strings(sample_rate, &TWELVE_STRING_FREQUENCIES)
+    }
+    
+    // One fret per string, low to high. Each string the chord plays replaces what that
+    // string was sounding, as play_note does, so calling it again restrikes the chord
+    // instead of stacking a second copy; strings it skips (-1) keep ringing. Frets past
+    // the last string, and frets the neck can't play, are ignored.
     pub fn play_chord(&mut self, fret_positions: &[i32]) {
-        self.active_notes.clear();
+        let string_count = fret_positions.len().min(self.open_string_frequencies.len());
+        self.held_chord = fret_positions[..string_count].to_vec();
+        self.strum(fret_positions, 1, 0.0);
+    }
+    
+    // Chord whose strings start one after another across duration_ms, as the pick
+    // sweeps them. direction >= 0 is a downstroke (low string first), negative an
+    // upstroke. A duration of 0 plays every string at once, like play_chord; it is
+    // capped at 1000 ms. Frets of -1 skip a string; MUTED_STRUM (-2) strums it muted.
+    // Strings are replaced the same way as by play_chord.
+    pub fn strum(&mut self, fret_positions: &[i32], direction: i32, duration_ms: f32) {
+        if !duration_ms.is_finite() {
+            return;
+        }
         
-        for (string_index, &fret) in fret_positions.iter().enumerate() {
-            if fret >= 0 {
+        let mut strings: Vec<(usize, i32)> = fret_positions
+            .iter()
+            .take(self.open_string_frequencies.len())
+            .enumerate()
+            .filter(|&(string_index, &fret)| fret == MUTED_STRUM || self.validate_fingering(string_index, fret).is_ok())
+            .map(|(string_index, &fret)| (string_index, fret))
+            .collect();
+        if direction < 0 {
+            strings.reverse();
+        }
+        
+        let spacing = if strings.len() > 1 {
+            duration_ms.clamp(0.0, 1000.0) / 1000.0 / (strings.len() - 1) as f32
+        } else {
+            0.0
+        };
+        for (order, (string_index, fret)) in strings.into_iter().enumerate() {
+            let mut note = if fret == MUTED_STRUM {
+                self.muted_strum_note(string_index)
+            } else {
                 let frequency = self.calculate_frequency(string_index, fret);
-                let note = GuitarNote {
-                    frequency,
-                    amplitude: self.pluck_strength,
-                    phase: 0.0,
-                    attack_time: 0.001,
-                    release_time: 2.0,
-                    start_time: self.current_time,
-                    duration: 3.0,
-                    harmonics: self.generate_harmonics(frequency),
-                    string_index,
-                };
-                self.active_notes.push(note);
-            }
+                self.create_note(string_index, frequency)
+            };
+            note.start_time += order as f32 * spacing;
+            self.start_note(note);
         }
     }
     
+    // Lenient: unplayable fingerings are silently ignored (see try_play_note)
     pub fn play_note(&mut self, string_index: usize, fret: i32) {
-        let frequency = self.calculate_frequency(string_index, fret);
-        let note = GuitarNote {
-            frequency,
-            amplitude: self.pluck_strength,
-            phase: 0.0,
-            attack_time: 0.001,
-            release_time: 2.0,
-            start_time: self.current_time,
-            duration: 3.0,
-            harmonics: self.generate_harmonics(frequency),
-            string_index,
+        self.play_note_with_duration(string_index, fret, DEFAULT_NOTE_DURATION);
+    }
+    
+    // duration is the whole note in seconds, release included, so a sequencer can set
+    // how long each note rings. Notes shorter than attack + release get a shortened
+    // release that still ramps down from wherever the attack reached.
+    pub fn play_note_with_duration(&mut self, string_index: usize, fret: i32, duration: f32) {
+        if self.validate_fingering(string_index, fret).is_err() {
+            return;
+        }
+        if !duration.is_finite() || duration <= 0.0 {
+            return;
+        }
+        
+        if self.legato_transition(string_index, fret, duration.min(MAX_NOTE_DURATION)) {
+            return;
+        }
+        
+        let mut note = self.fretted_note(string_index, fret, 1.0);
+        note.set_length(duration.min(MAX_NOTE_DURATION));
+        self.start_note(note);
+    }
+    
+    // Like play_note, but reports a string index or fret that can't be played
+    pub fn try_play_note(&mut self, string_index: usize, fret: i32) -> Result<(), String> {
+        self.validate_fingering(string_index, fret)?;
+        self.play_note_with_velocity(string_index, fret, 1.0);
+        Ok(())
+    }
+    
+    // Velocity 0.0..=1.0 scales the pluck level; softer plucks are also darker
+    pub fn play_note_with_velocity(&mut self, string_index: usize, fret: i32, velocity: f32) {
+        if self.validate_fingering(string_index, fret).is_err() || !velocity.is_finite() {
+            return;
+        }
+        
+        if self.legato_transition(string_index, fret, DEFAULT_NOTE_DURATION) {
+            return;
+        }
+        
+        let note = self.fretted_note(string_index, fret, velocity);
+        self.start_note(note);
+    }
+    
+    // Plays a pitch by name, e.g. ("C#", 4), as a free voice not tied to any string.
+    // Unrecognised names and octaves outside -1..=9 are ignored.
+    pub fn play_note_by_name(&mut self, note: &str, octave: i32) {
+        let Some(pitch_class) = parse_note_name(note) else {
+            return;
         };
+        if !(-1..=9).contains(&octave) {
+            return;
+        }
+        let midi_note = (octave + 1) * 12 + pitch_class;
+        let frequency = midi_to_frequency(midi_note as f32, self.concert_pitch);
+        let note = self.create_note(FREE_VOICE, frequency);
+        self.start_note(note);
+    }
+    
+    // Free voice at any pitch, bypassing tuning and capo, for just intonation and other
+    // microtonal tunings. duration is the whole note in seconds, release included.
+    pub fn play_frequency(&mut self, freq: f32, duration: f32) {
+        if !freq.is_finite() || freq <= 0.0 || freq >= self.sample_rate / 2.0 {
+            return;
+        }
+        if !duration.is_finite() || duration <= 0.0 {
+            return;
+        }
         
-        // Remove any existing note on the same string
-        self.active_notes.retain(|n| n.string_index != string_index);
-        self.active_notes.push(note);
+        let mut note = self.create_note(FREE_VOICE, freq);
+        note.set_length(duration.min(MAX_NOTE_DURATION));
+        self.start_note(note);
     }
     
-    pub fn stop_all(&mut self) {
-        self.active_notes.clear();
+    // Volume-pedal swell: the note fades in over swell_time seconds (at most the note's
+    // length) instead of starting with the pick attack. The release gives way to the
+    // swell, down to half the note's length.
+    pub fn play_note_swell(&mut self, string_index: usize, fret: i32, swell_time: f32) {
+        if self.validate_fingering(string_index, fret).is_err() || !swell_time.is_finite() {
+            return;
+        }
+        
+        let mut note = self.fretted_note(string_index, fret, 1.0);
+        note.attack_time = swell_time.clamp(note.attack_time, note.duration);
+        note.set_length(note.duration);
+        self.start_note(note);
     }
     
-    pub fn process(&mut self, output_buffer: &mut [f32]) {
-        for sample in output_buffer.iter_mut() {
-            let mut output = 0.0;
+    pub fn play_note_muted(&mut self, string_index: usize, fret: i32) {
+        if self.validate_fingering(string_index, fret).is_err() {
+            return;
+        }
+        
+        let frequency = self.calculate_frequency(string_index, fret);
+        let mut note = self.create_note(string_index, frequency);
+        note.palm_mute = true;
+        note.release_time = PALM_MUTE_RELEASE;
+        note.duration = PALM_MUTE_DURATION;
+        note.harmonics = self.generate_harmonics(&note);
+        self.start_note(note);
+    }
+    
+    // Flageolet tone from lightly touching the string over node_fret (relative to the
+    // capo): only the partial with a node there rings, with an almost pure timbre.
+    // Frets without a usable node play as an ordinary fretted note.
+    pub fn play_harmonic(&mut self, string_index: usize, node_fret: i32) {
+        let Some(partial) = harmonic_at_node(node_fret) else {
+            self.play_note(string_index, node_fret);
+            return;
+        };
+        if self.validate_fingering(string_index, node_fret).is_err() {
+            return;
+        }
+        
+        let frequency = self.calculate_frequency(string_index, 0) * partial;
+        let mut note = self.create_note(string_index, frequency);
+        note.flageolet = true;
+        note.harmonics = self.generate_harmonics(&note);
+        self.start_note(note);
+    }
+    
+    // Glide the note ringing on a string by the given number of semitones (negative to
+    // release a bend, +-24 at most) over duration seconds. No-op when the string is silent.
+    pub fn bend_note(&mut self, string_index: usize, semitones: f32, duration: f32) {
+        if !(semitones.is_finite() && duration.is_finite()) {
+            return;
+        }
+        let semitones = semitones.clamp(-MAX_PITCH_BEND, MAX_PITCH_BEND);
+        if let Some(note) = self.active_notes.iter_mut().find(|n| n.string_index == string_index) {
+            note.bend_target = note.frequency * 2.0_f32.powf(semitones / 12.0);
+            if duration <= 0.0 {
+                note.frequency = note.bend_target;
+                note.bend_rate = 0.0;
+            } else {
+                note.bend_rate = (note.bend_target - note.frequency) / duration;
+            }
+        }
+    }
+    
+    // Polyphonic aftertouch for MPE-style controllers: pressure 0.0-1.0 on the note
+    // ringing on a string swells its sustain level and brightens it while held; the
+    // attack is left alone. 0.0 is neutral. No-op when the string is silent.
+    pub fn set_note_pressure(&mut self, string_index: usize, pressure: f32) {
+        if !pressure.is_finite() {
+            return;
+        }
+        let current_time = self.current_time;
+        let sounding = self.active_notes.iter_mut().find(|n| n.string_index == string_index && n.start_time <= current_time);
+        if let Some(note) = sounding {
+            note.pressure_target = pressure.clamp(0.0, 1.0);
+        }
+    }
+    
+    // Slide the ringing note to target_fret over duration_ms without picking again. The
+    // pitch moves a fret at a time, as it does under a real slide, with a faint click at
+    // each fret. No-op when the string is silent.
+    pub fn slide_note(&mut self, string_index: usize, target_fret: i32, duration_ms: f32) {
+        if !duration_ms.is_finite() {
+            return;
+        }
+        if self.validate_fingering(string_index, target_fret).is_err() {
+            return;
+        }
+        let target = self.calculate_frequency(string_index, target_fret);
+        let Some(note) = self.active_notes.iter_mut().find(|n| n.string_index == string_index) else {
+            return;
+        };
+        
+        let frets = (12.0 * (target / note.frequency).log2()).round() as i32;
+        note.bend_target = target;
+        note.bend_rate = 0.0;
+        if frets == 0 || duration_ms <= 0.0 {
+            note.frequency = target;
+            note.slide_frets_left = 0;
+            return;
+        }
+        note.slide_frets_left = frets.unsigned_abs();
+        note.slide_ratio = 2.0_f32.powf(frets.signum() as f32 / 12.0);
+        note.slide_fret_time = duration_ms / 1000.0 / frets.unsigned_abs() as f32;
+        note.slide_clock = 0.0;
+    }
+    
+    pub fn hammer_on(&mut self, string_index: usize, new_fret: i32) {
+        self.change_fret(string_index, new_fret, HAMMER_ON_GAIN);
+    }
+    
+    pub fn pull_off(&mut self, string_index: usize, new_fret: i32) {
+        self.change_fret(string_index, new_fret, PULL_OFF_GAIN);
+    }
+    
+    // Note-off: the ringing note on a string fades out over its release time from
+    // wherever its envelope is now. Ignored if the string is silent or already releasing;
+    // a note still waiting for its strum offset is dropped.
+    pub fn release_note(&mut self, string_index: usize) {
+        self.release_voices(|n| n.string_index == string_index);
+    }
+    
+    // MIDI keyboard input: plays the key at the lowest-fret position the tuning offers
+    // (see fret_position_for_frequency) on a string that isn't sounding, with velocity
+    // 1-127, so chords get a string per key. Only when every string that can reach the
+    // key is busy does it take one over. Velocity 0 is a note-off, and keys outside the
+    // neck's range are ignored.
+    pub fn midi_note_on(&mut self, note: u8, velocity: u8) {
+        if velocity == 0 {
+            self.midi_note_off(note);
+            return;
+        }
+        let frequency = midi_to_frequency(note as f32, self.concert_pitch);
+        let position = self
+            .fret_position_among(frequency, |string_index| !self.string_sounding(string_index))
+            .or_else(|| self.fret_position_for_frequency(frequency));
+        let Some((string_index, fret)) = position else {
+            return;
+        };
+        
+        let mut voice = self.fretted_note(string_index, fret, velocity.min(127) as f32 / 127.0);
+        voice.midi_note = Some(note);
+        self.start_note(voice);
+    }
+    
+    // Releases the voice started by midi_note_on for this key, if it's still sounding
+    pub fn midi_note_off(&mut self, note: u8) {
+        self.release_voices(|n| n.midi_note == Some(note));
+    }
+    
+    // While the pedal is down, release_note and midi_note_off only mark notes; lifting
+    // it releases all of them together
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        let lifted = self.sustain_pedal && !down;
+        self.sustain_pedal = down;
+        if lifted {
+            self.release_voices(|n| n.pending_release);
+        }
+    }
+    
+    // Queue a note to start at an absolute time in seconds on the synth's clock and last
+    // duration seconds, release included. process starts it on the exact sample, so a
+    // riff scheduled ahead plays back without timer jitter. A start time already passed
+    // plays at once.
+    pub fn schedule_note(&mut self, string_index: usize, fret: i32, start_time: f32, duration: f32) {
+        if self.validate_fingering(string_index, fret).is_err() {
+            return;
+        }
+        if !start_time.is_finite() || !duration.is_finite() || duration <= 0.0 {
+            return;
+        }
+        
+        let scheduled = ScheduledNote { string_index, fret, start_time, duration: duration.min(MAX_NOTE_DURATION) };
+        let position = self.scheduled_notes.partition_point(|n| n.start_time <= start_time);
+        self.scheduled_notes.insert(position, scheduled);
+    }
+    
+    // Queue a riff written as ASCII tab (see parse_tab) to start now, each character
+    // column a 16th note at tempo_bpm. Each note rings until the next one on its string
+    // (or for DEFAULT_NOTE_DURATION). Frets that can't be played are skipped.
+    pub fn play_tab(&mut self, tab: &str, tempo_bpm: f32) {
+        if !tempo_bpm.is_finite() || tempo_bpm <= 0.0 {
+            return;
+        }
+        let column_time = 60.0 / tempo_bpm.clamp(20.0, 400.0) * TAB_COLUMN_BEATS;
+        let start_time = self.current_time;
+        for (string_index, fret, column) in parse_tab(tab, self.open_string_frequencies.len()) {
+            self.schedule_note(string_index, fret, start_time + column as f32 * column_time, DEFAULT_NOTE_DURATION);
+        }
+    }
+    
+    // Seconds of audio rendered since creation or the last reset_time, the clock that
+    // schedule_note start times are measured on
+    pub fn get_current_time(&self) -> f32 {
+        self.current_time
+    }
+    
+    // Restart the clock at 0 between pieces, or to win back float precision after a long
+    // session. Sounding notes, queued notes and the metronome bar are shifted with it,
+    // so nothing audible changes.
+    pub fn reset_time(&mut self) {
+        let offset = self.current_time;
+        for note in &mut self.active_notes {
+            note.start_time -= offset;
+        }
+        for scheduled in &mut self.scheduled_notes {
+            scheduled.start_time -= offset;
+        }
+        self.metronome_start -= offset;
+        self.arpeggiator_next -= offset;
+        self.current_time = 0.0;
+    }
+    
+    // Also drops notes queued with schedule_note and the chord the arpeggiator plays
+    pub fn stop_all(&mut self) {
+        self.active_notes.clear();
+        self.scheduled_notes.clear();
+        self.held_chord.clear();
+    }
+    
+    // Click-free stop: every sounding note ramps to silence over fade_ms and is then
+    // dropped. Strum and scheduled notes that haven't started yet are cancelled, and the
+    // arpeggiator lets go of its chord.
+    pub fn stop_all_smooth(&mut self, fade_ms: f32) {
+        if !fade_ms.is_finite() {
+            return;
+        }
+        self.scheduled_notes.clear();
+        self.held_chord.clear();
+        let fade = (fade_ms / 1000.0).clamp(0.001, 10.0);
+        let current_time = self.current_time;
+        self.active_notes.retain(|n| n.start_time <= current_time);
+        for note in self.active_notes.iter_mut() {
+            note.fade_out(current_time - note.start_time, fade);
+        }
+    }
+    
+    // Palm laid across the strings: each sounding string is damped over STRING_MUTE_FADE,
+    // one after another from string 0 up, the last starting duration_ms from now. 0
+    // damps them all at once. Free voices are damped first, and notes of a strum that
+    // haven't started yet are left to play.
+    pub fn mute_strings(&mut self, duration_ms: f32) {
+        if !duration_ms.is_finite() {
+            return;
+        }
+        
+        let current_time = self.current_time;
+        let mut strings: Vec<usize> = self
+            .active_notes
+            .iter()
+            .filter(|n| n.start_time <= current_time && n.string_index != FREE_VOICE)
+            .map(|n| n.string_index)
+            .collect();
+        strings.sort_unstable();
+        strings.dedup();
+        let spacing = if strings.len() > 1 {
+            duration_ms.clamp(0.0, 1000.0) / 1000.0 / (strings.len() - 1) as f32
+        } else {
+            0.0
+        };
+        
+        for note in self.active_notes.iter_mut().filter(|n| n.start_time <= current_time) {
+            let order = strings.iter().position(|&s| s == note.string_index).unwrap_or(0);
+            note.fade_out(current_time - note.start_time + order as f32 * spacing, STRING_MUTE_FADE);
+        }
+    }
+    
+    // Replaying a ringing string normally cuts the old note off, which can click. When
+    // enabled the old note is faded out over RETRIGGER_FADE underneath the new one.
+    pub fn set_smooth_retrigger(&mut self, enabled: bool) {
+        self.smooth_retrigger = enabled;
+    }
+    
+    // Length of that choke in milliseconds (1-50); a few ms sounds like the pick
+    // stopping the string. Any positive length turns smooth retrigger on, 0 turns it off.
+    pub fn set_retrigger_choke_ms(&mut self, ms: f32) {
+        if !ms.is_finite() {
+            return;
+        }
+        self.smooth_retrigger = ms > 0.0;
+        if ms > 0.0 {
+            self.retrigger_fade = ms.clamp(1.0, 50.0) / 1000.0;
+        }
+    }
+    
+    // Monophonic lead mode: playing a string that is still ringing moves the note to the
+    // new fret like a hammer-on, without re-picking or restarting its envelope. Applies
+    // to play_note and its variants, not to chords or strums.
+    pub fn set_legato(&mut self, enabled: bool) {
+        self.legato = enabled;
+    }
+    
+    // Cap on simultaneous voices; new notes steal the quietest one. 0 means unlimited.
+    pub fn set_max_voices(&mut self, max: usize) {
+        self.max_voices = if max == 0 { usize::MAX } else { max };
+    }
+    
+    pub fn active_voice_count(&self) -> usize {
+        self.active_notes.len()
+    }
+    
+    // Envelope level (0.0-1.0) of every active note, as flat [string_index, level, ...]
+    // pairs for meters. Voices not bound to a string report index -1; strum notes that
+    // haven't started yet report 0.0.
+    pub fn voice_envelopes(&self) -> Vec<f32> {
+        let mut envelopes = Vec::with_capacity(self.active_notes.len() * 2);
+        for note in &self.active_notes {
+            let elapsed = self.current_time - note.start_time;
+            let level = if elapsed < 0.0 { 0.0 } else { note.envelope(elapsed) };
+            let string_index = if note.string_index == FREE_VOICE { -1.0 } else { note.string_index as f32 };
+            envelopes.push(string_index);
+            envelopes.push(level.clamp(0.0, 1.0));
+        }
+        envelopes
+    }
+    
+    // Debug snapshot of every active note as a JSON array of objects with string_index
+    // (null for free voices), frequency, amplitude (including the envelope), elapsed
+    // seconds and stage ("pending", "attack", "decay", "sustain" or "release")
+    pub fn active_notes_info(&self) -> String {
+        let notes: Vec<String> = self
+            .active_notes
+            .iter()
+            .map(|note| {
+                let elapsed = self.current_time - note.start_time;
+                let (level, stage) = if elapsed < 0.0 {
+                    (0.0, "pending")
+                } else {
+                    (note.envelope(elapsed), note.envelope_stage(elapsed))
+                };
+                let string_index = if note.string_index == FREE_VOICE {
+                    "null".to_string()
+                } else {
+                    note.string_index.to_string()
+                };
+                format!(
+                    "{{\"string_index\":{},\"frequency\":{},\"amplitude\":{},\"elapsed\":{},\"stage\":\"{}\"}}",
+                    string_index,
+                    json::number(note.frequency),
+                    json::number(note.amplitude * level),
+                    json::number(elapsed),
+                    stage
+                )
+            })
+            .collect();
+        format!("[{}]", notes.join(","))
+    }
+    
+    // Switch to the host's real output rate (8 kHz to 384 kHz) when it wasn't known at
+    // construction, e.g. a WebAudio context. Ringing strings, filters and effect delay
+    // lines are rescaled so pitches and times stay put; effect tails restart.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if !sample_rate.is_finite() || sample_rate <= 0.0 {
+            return;
+        }
+        let sample_rate = sample_rate.clamp(8000.0, 384000.0);
+        let old_rate = self.sample_rate;
+        if sample_rate == old_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        
+        for note in self.active_notes.iter_mut() {
+            note.rescale_string(sample_rate);
+        }
+        
+        let tone_cutoff = if self.tone_coefficient >= 1.0 { sample_rate / 2.0 } else { self.tone_cutoff };
+        self.set_tone(tone_cutoff);
+        let [low, mid, high] = self.eq_gains;
+        self.set_eq(low, mid, high);
+        self.body_coefficients = BODY_MODES.map(|(frequency, q, _)| resonator_coefficients(sample_rate, frequency, q));
+        self.compressor_attack = self.compressor_attack.powf(old_rate / sample_rate);
+        self.compressor_release = self.compressor_release.powf(old_rate / sample_rate);
+        self.envelope_wah_attack = self.envelope_wah_attack.powf(old_rate / sample_rate);
+        self.envelope_wah_release = self.envelope_wah_release.powf(old_rate / sample_rate);
+        
+        if !self.delay_buffers[0].is_empty() {
+            let time_seconds = self.delay_buffers[0].len() as f32 / old_rate;
+            self.set_delay(time_seconds, self.delay_feedback, self.delay_mix);
+        }
+        if !self.chorus_buffers[0].is_empty() {
+            self.chorus_buffers.iter_mut().for_each(Vec::clear);
+            self.set_chorus(self.chorus_rate, self.chorus_depth * 1000.0, self.chorus_mix);
+        }
+        if !self.reverb_combs[0].is_empty() {
+            self.reverb_combs = [Vec::new(), Vec::new()];
+            let room_size = (self.reverb_feedback - 0.7) / 0.28;
+            self.set_reverb(room_size, self.reverb_damping / 0.4, self.reverb_mix, self.reverb_width);
+        }
+        self.set_stereo_width(self.haas_buffer.len() as f32 / old_rate * 1000.0);
+        
+        // Recorded loops are resampled so they keep their pitch and length in seconds
+        if !self.loop_buffers[0].is_empty() {
+            let ratio = sample_rate / old_rate;
+            let length = (self.loop_buffers[0].len() as f32 * ratio) as usize;
+            let capacity = (MAX_LOOP_SECONDS * sample_rate) as usize;
+            for buffer in self.loop_buffers.iter_mut() {
+                let mut resampled = resample_linear(buffer, length);
+                if self.loop_recording && self.loop_length == 0 {
+                    resampled.reserve(capacity.saturating_sub(length));
+                }
+                *buffer = resampled;
+            }
+            if self.loop_length > 0 {
+                self.loop_length = length.max(1);
+                self.loop_position = ((self.loop_position as f32 * ratio) as usize).min(self.loop_length - 1);
+            }
+        }
+    }
+    
+    // 0 = Karplus-Strong plucked string (default), 1 = additive sine harmonics.
+    // Only affects notes played after the call.
+    pub fn set_synthesis_mode(&mut self, mode: u32) {
+        if let Some(mode) = SynthesisMode::from_u32(mode) {
+            self.synthesis_mode = mode;
+        }
+    }
+    
+    pub fn process(&mut self, output_buffer: &mut [f32]) {
+        let mut mix = [0.0; MIX_BLOCK];
+        let mut times = [0.0; MIX_BLOCK];
+        let mut peak: f32 = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut offset = 0;
+        while offset < output_buffer.len() {
+            self.start_scheduled_notes();
+            self.advance_arpeggiator();
+            let frames = self.block_length(output_buffer.len() - offset);
+            let block = &mut output_buffer[offset..offset + frames];
+            offset += frames;
             
-            // Process each active note
-            let mut i = 0;
-            while i < self.active_notes.len() {
-                let elapsed = self.current_time - self.active_notes[i].start_time;
-                
-                if elapsed > self.active_notes[i].duration {
-                    self.active_notes.remove(i);
-                    continue;
+            mix[..frames].fill(0.0);
+            self.mix_voices(&mut times[..frames], &mut [&mut mix[..frames]]);
+            for (frame, sample) in block.iter_mut().enumerate() {
+                let output = self.finish_sample(0, mix[frame], times[frame]);
+                let output = self.apply_reverb(output);
+                let output = self.apply_looper(output, output).0;
+                *sample = self.apply_noise_gate(output, output).0;
+                self.record_history(*sample);
+                peak = peak.max(sample.abs());
+                sum_of_squares += *sample * *sample;
+                self.advance_effects();
+            }
+        }
+        self.update_levels(peak, sum_of_squares, output_buffer.len());
+    }
+    
+    // Stereo render with each string placed by set_string_pan. Renders as many frames
+    // as the shorter of the two buffers.
+    pub fn process_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let frames = left.len().min(right.len());
+        let mut left_mix = [0.0; MIX_BLOCK];
+        let mut right_mix = [0.0; MIX_BLOCK];
+        let mut times = [0.0; MIX_BLOCK];
+        let mut peak: f32 = 0.0;
+        let mut sum_of_squares = 0.0;
+        let total_frames = frames;
+        let mut offset = 0;
+        while offset < total_frames {
+            self.start_scheduled_notes();
+            self.advance_arpeggiator();
+            let frames = self.block_length(total_frames - offset);
+            let left_block = &mut left[offset..offset + frames];
+            let right_block = &mut right[offset..offset + frames];
+            offset += frames;
+            
+            left_mix[..frames].fill(0.0);
+            right_mix[..frames].fill(0.0);
+            self.mix_voices(&mut times[..frames], &mut [&mut left_mix[..frames], &mut right_mix[..frames]]);
+            for frame in 0..frames {
+                let left_sample = self.finish_sample(0, left_mix[frame], times[frame]);
+                let right_sample = self.finish_sample(1, right_mix[frame], times[frame]);
+                let (left_sample, right_sample) = self.apply_reverb_stereo(left_sample, right_sample);
+                let right_sample = self.apply_haas(right_sample);
+                let (left_sample, right_sample) = self.apply_looper(left_sample, right_sample);
+                let (left_sample, right_sample) = self.apply_noise_gate(left_sample, right_sample);
+                self.record_history(0.5 * (left_sample + right_sample));
+                left_block[frame] = left_sample;
+                right_block[frame] = right_sample;
+                peak = peak.max(left_sample.abs()).max(right_sample.abs());
+                sum_of_squares += left_sample * left_sample + right_sample * right_sample;
+                self.advance_effects();
+            }
+        }
+        self.update_levels(peak, sum_of_squares, total_frames * 2);
+    }
+    
+    // Largest absolute sample of the last process, process_stereo or process_multi call
+    // (all channels)
+    pub fn last_buffer_peak(&self) -> f32 {
+        self.last_buffer_peak
+    }
+    
+    // RMS level over the last process, process_stereo or process_multi call (all channels)
+    pub fn last_buffer_rms(&self) -> f32 {
+        self.last_buffer_rms
+    }
+    
+    // True once nothing will sound until the next note: no voices or scheduled notes, no
+    // metronome, arpeggio or loop playback, and the last buffer plus every delay, chorus
+    // and reverb line below SILENCE_THRESHOLD. Lines of an effect whose mix is 0 aren't
+    // heard, so they don't count. A host can stop calling process until it plays
+    // something again.
+    pub fn is_silent(&self) -> bool {
+        let quiet = |buffer: &[f32]| buffer.iter().all(|s| s.abs() < SILENCE_THRESHOLD);
+        let quiet_wide = |buffer: &[f64]| buffer.iter().all(|s| s.abs() < f64::from(SILENCE_THRESHOLD));
+        let arpeggiating = self.arpeggiator_enabled && !self.held_chord.is_empty();
+        let looping = self.loop_playing && self.loop_length > 0;
+        let delay_quiet = self.delay_mix == 0.0
+            || (self.delay_buffers.iter().all(|buffer| quiet(buffer))
+                && self.delay_wide_buffers.iter().all(|buffer| quiet_wide(buffer)));
+        let chorus_quiet = self.chorus_mix == 0.0 || self.chorus_buffers.iter().all(|buffer| quiet(buffer));
+        let reverb_quiet = self.reverb_mix == 0.0
+            || (self.reverb_combs.iter().flatten().all(|comb| quiet(&comb.buffer) && quiet_wide(&comb.wide_buffer))
+                && self.reverb_allpasses.iter().flatten()
+                    .all(|allpass| quiet(&allpass.buffer) && quiet_wide(&allpass.wide_buffer)));
+        self.active_notes.is_empty()
+            && self.scheduled_notes.is_empty()
+            && !self.metronome_enabled
+            && !arpeggiating
+            && !looping
+            && self.last_buffer_peak < SILENCE_THRESHOLD
+            && delay_quiet
+            && chorus_quiet
+            && reverb_quiet
+            && quiet(&self.haas_buffer)
+    }
+    
+    // Pan: -1.0 is hard left, 1.0 hard right, 0.0 (default) center
+    pub fn set_string_pan(&mut self, string_index: usize, pan: f32) {
+        if !pan.is_finite() {
+            return;
+        }
+        if let Some(string_pan) = self.string_pans.get_mut(string_index) {
+            *string_pan = pan.clamp(-1.0, 1.0);
+        }
+    }
+    
+    // Practice mutes: a muted string still takes notes but isn't heard, and once any
+    // string is soloed only the soloed ones are (voices not bound to a string included).
+    // Unsoloing the last string brings everything back.
+    pub fn set_string_muted(&mut self, string_index: usize, muted: bool) {
+        if let Some(string_muted) = self.string_muted.get_mut(string_index) {
+            *string_muted = muted;
+        }
+    }
+    
+    pub fn set_string_solo(&mut self, string_index: usize, solo: bool) {
+        if let Some(string_soloed) = self.string_soloed.get_mut(string_index) {
+            *string_soloed = solo;
+        }
+    }
+    
+    // Output a string plays through in process_multi, wrapped around when it's past the
+    // channel count. String n starts on channel n.
+    pub fn set_string_channel(&mut self, string_index: usize, channel: usize) {
+        if let Some(string_channel) = self.string_channels.get_mut(string_index) {
+            *string_channel = channel;
+        }
+    }
+    
+    // How far pluck strength pulls a note from its string's pan toward the center
+    // (0-1): each note sits at pan * (1 - amount * velocity), so soft notes spread
+    // wide and hard ones gather in the middle. 0.0 (default) keeps the static pan.
+    pub fn set_dynamic_pan(&mut self, amount: f32) {
+        if amount.is_finite() {
+            self.dynamic_pan = amount.clamp(0.0, 1.0);
+        }
+    }
+    
+    // Number of strings, for drawing the neck: 6 by default, or however many
+    // new_with_strings was given
+    pub fn string_count(&self) -> usize {
+        self.open_string_frequencies.len()
+    }
+    
+    // Pitch in Hz the string sounds unfretted with the current tuning and capo (0.0 for
+    // a string index out of range)
+    pub fn open_frequency(&self, string_index: usize) -> f32 {
+        self.calculate_frequency(string_index, 0)
+    }
+    
+    // Pitch in Hz of a fingering with the current tuning and capo (0.0 if unplayable)
+    pub fn get_frequency(&self, string_index: usize, fret: i32) -> f32 {
+        self.calculate_frequency(string_index, fret)
+    }
+    
+    // Note name of a fingering, e.g. "A4"; empty if unplayable
+    pub fn get_note_name(&self, string_index: usize, fret: i32) -> String {
+        frequency_to_note_name(self.calculate_frequency(string_index, fret), self.concert_pitch).unwrap_or_default()
+    }
+    
+    // Name of the chord a fingering sounds in the current tuning, e.g. "Cmaj7". Negative
+    // frets are muted strings. When several roots fit (Csus2 is also Gsus4) the bass note
+    // wins. None if the pitch classes don't match a known chord exactly.
+    pub fn identify_chord(&self, fret_positions: &[i32]) -> Option<String> {
+        let mut pitch_classes = [false; 12];
+        let mut bass = None;
+        for (string_index, &fret) in fret_positions.iter().enumerate() {
+            let frequency = self.calculate_frequency(string_index, fret);
+            if frequency <= 0.0 {
+                continue;
+            }
+            let midi_note = frequency_to_midi(frequency, self.concert_pitch).round() as i32;
+            pitch_classes[midi_note.rem_euclid(12) as usize] = true;
+            if bass.is_none_or(|lowest| midi_note < lowest) {
+                bass = Some(midi_note);
+            }
+        }
+        
+        let bass = bass?.rem_euclid(12);
+        let roots = std::iter::once(bass).chain((0..12).filter(|&root| root != bass));
+        for root in roots {
+            if !pitch_classes[root as usize] {
+                continue;
+            }
+            for (suffix, intervals) in CHORD_QUALITIES {
+                let matches = (0..12).all(|interval| {
+                    pitch_classes[((root + interval) % 12) as usize] == intervals.contains(&interval)
+                });
+                if matches {
+                    return Some(format!("{}{}", NOTE_NAMES[root as usize], suffix));
                 }
-                
-                // Calculate envelope (using immutable borrow)
-                let attack_time = self.active_notes[i].attack_time;
-                let release_time = self.active_notes[i].release_time;
-                let duration = self.active_notes[i].duration;
-                let envelope = self.calculate_envelope(elapsed, attack_time, release_time, duration);
-                
-                // Generate sound using Karplus-Strong algorithm simulation
-                let mut signal = 0.0;
-                
-                // Get note data for calculations
-                let phase = self.active_notes[i].phase;
-                let frequency = self.active_notes[i].frequency;
-                let amplitude = self.active_notes[i].amplitude;
-                let harmonics = self.active_notes[i].harmonics.clone();
-                
-                // Fundamental frequency
-                signal += (phase * 2.0 * PI).sin() * amplitude;
-                
-                // Add harmonics for more realistic guitar sound
-                for (harmonic_index, &harmonic_amp) in harmonics.iter().enumerate() {
-                    let harmonic_phase = phase * (harmonic_index + 2) as f32;
-                    signal += (harmonic_phase * 2.0 * PI).sin() * harmonic_amp * amplitude;
+            }
+        }
+        None
+    }
+    
+    // Fret positions (-1 for unplayed strings) for a chord in the current tuning, ready
+    // for play_chord or strum. quality is a suffix known to identify_chord ("", "m",
+    // "7", ...; "maj" and "min" also work). The shape stays within VOICING_SPAN frets
+    // from position, using open strings only at position 0. Among shapes with the root
+    // in the bass and every chord tone present, the one with the most adjacent strings,
+    // then the fewest fingers (a barre counts as one), then the lowest frets wins.
+    // Empty if the chord is unknown or has no such shape there.
+    pub fn chord_voicing(&self, root: &str, quality: &str, position: i32) -> Vec<i32> {
+        let Some(root) = parse_note_name(root) else {
+            return Vec::new();
+        };
+        let quality = match quality {
+            "maj" => "",
+            "min" => "m",
+            other => other,
+        };
+        let Some(&(_, intervals)) = CHORD_QUALITIES.iter().find(|(suffix, _)| *suffix == quality) else {
+            return Vec::new();
+        };
+        let highest_fret = self.fret_count - self.capo;
+        if !(0..=highest_fret).contains(&position) {
+            return Vec::new();
+        }
+        
+        // Frets on each string under the hand that sound a chord tone, with its interval
+        let string_count = self.open_string_frequencies.len();
+        let lowest_fret = if position == 0 { 0 } else { position };
+        let choices: Vec<Vec<(i32, i32)>> = (0..string_count)
+            .map(|string_index| {
+                let top_fret = (position + VOICING_SPAN - 1).min(self.fret_count - self.string_capo(string_index));
+                (lowest_fret..=top_fret)
+                    .filter_map(|fret| {
+                        let frequency = self.calculate_frequency(string_index, fret);
+                        let midi_note = frequency_to_midi(frequency, self.concert_pitch).round() as i32;
+                        let interval = (midi_note - root).rem_euclid(12);
+                        intervals.contains(&interval).then_some((fret, interval))
+                    })
+                    .collect()
+            })
+            .collect();
+        
+        let mut best: Option<(Vec<i32>, (usize, i32, i32))> = None;
+        for low in 0..string_count {
+            for high in (low + VOICING_MIN_STRINGS - 1)..string_count {
+                let strings = &choices[low..=high];
+                if strings.iter().any(|frets| frets.is_empty()) {
+                    continue;
                 }
-                
-                // Apply envelope and damping
-                signal *= envelope * amplitude;
-                output += signal;
-                
-                // Update phase (now using mutable borrow)
-                let note = &mut self.active_notes[i];
-                note.phase += note.frequency / self.sample_rate;
-                if note.phase > 1.0 {
-                    note.phase -= 1.0;
+                // Every combination of one candidate fret per string, odometer style
+                let mut picks = vec![0; strings.len()];
+                loop {
+                    let shape: Vec<(i32, i32)> = strings.iter().zip(&picks).map(|(frets, &pick)| frets[pick]).collect();
+                    let complete = intervals.iter().all(|interval| shape.iter().any(|&(_, i)| i == *interval));
+                    if shape[0].1 == 0 && complete {
+                        let fretted: Vec<i32> = shape.iter().map(|&(fret, _)| fret).filter(|&fret| fret > 0).collect();
+                        let barre = fretted.iter().copied().min();
+                        let fingers = fretted.iter().filter(|&&fret| Some(fret) != barre).count() as i32
+                            + i32::from(barre.is_some());
+                        let fret_sum: i32 = fretted.iter().sum();
+                        let score = (shape.len(), -fingers, -fret_sum);
+                        if fingers <= 4 && best.as_ref().is_none_or(|(_, best_score)| score > *best_score) {
+                            let mut frets = vec![-1; string_count];
+                            for (offset, &(fret, _)) in shape.iter().enumerate() {
+                                frets[low + offset] = fret;
+                            }
+                            best = Some((frets, score));
+                        }
+                    }
+                    
+                    let Some(digit) = (0..picks.len()).find(|&d| picks[d] + 1 < strings[d].len()) else {
+                        break;
+                    };
+                    picks[digit] += 1;
+                    picks[..digit].fill(0);
                 }
-                
-                // Apply string damping
-                note.amplitude *= self.string_damping;
-                
-                i += 1;
             }
-            
-            // Soft clipping to prevent distortion
-            *sample = self.soft_clip(output * 0.3);
-            
-            self.current_time += 1.0 / self.sample_rate;
         }
+        best.map(|(frets, _)| frets).unwrap_or_default()
+    }
+    
+    // Magnitude spectrum of the most recent output, for an analyzer display: a
+    // Hann-windowed FFT over the last fft_size samples (rounded down to a power of two,
+    // 16 to SPECTRUM_HISTORY), returning fft_size / 2 bins from DC up, each
+    // sample_rate / fft_size wide. Scaled so a full-scale sine peaks near 1.0.
+    pub fn spectrum(&self, fft_size: usize) -> Vec<f32> {
+        if fft_size < 16 {
+            return Vec::new();
+        }
+        let size = 1 << fft_size.min(SPECTRUM_HISTORY).ilog2();
+        
+        let oldest = self.history_position + SPECTRUM_HISTORY - size;
+        let mut re: Vec<f32> = (0..size)
+            .map(|k| {
+                let window = 0.5 - 0.5 * (2.0 * PI * k as f32 / size as f32).cos();
+                self.output_history[(oldest + k) % SPECTRUM_HISTORY] * window
+            })
+            .collect();
+        let mut im = vec![0.0; size];
+        fft(&mut re, &mut im);
+        
+        // The Hann window's coherent gain is 0.5, and a real sine splits across two bins
+        let scale = 4.0 / size as f32;
+        re.iter().zip(&im).take(size / 2).map(|(re, im)| (re * re + im * im).sqrt() * scale).collect()
+    }
+    
+    // Estimated fundamental in Hz of the most recent output (the last SPECTRUM_HISTORY
+    // samples), for a tuner display. None when it's too quiet or not clearly pitched,
+    // e.g. during a strummed chord.
+    pub fn detect_pitch(&self) -> Option<f32> {
+        let recent: Vec<f32> = (0..SPECTRUM_HISTORY)
+            .map(|k| self.output_history[(self.history_position + k) % SPECTRUM_HISTORY])
+            .collect();
+        let mean_square = recent.iter().map(|s| s * s).sum::<f32>() / recent.len() as f32;
+        if mean_square.sqrt() < PITCH_MIN_RMS {
+            return None;
+        }
+        yin_pitch(&recent, self.sample_rate)
+    }
+    
+    // Allocating counterpart of process for offline rendering and native hosts
+    pub fn render(&mut self, num_samples: usize) -> Vec<f32> {
+        let mut samples = vec![0.0; num_samples];
+        self.process(&mut samples);
+        samples
+    }
+    
+    // What the next num_samples of render would return, without consuming them: the
+    // preview runs on a copy, so the clock, voices and effect tails are left as they
+    // were. For oscilloscope-style displays; allocates, so keep it off the audio thread.
+    pub fn peek(&self, num_samples: usize) -> Vec<f32> {
+        self.clone().render(num_samples)
+    }
+    
+    // Render num_samples through process and return them as a 16-bit mono WAV file
+    pub fn render_to_wav(&mut self, num_samples: usize) -> Vec<u8> {
+        let samples = self.render(num_samples);
+        encode_wav(&samples, self.sample_rate as u32)
     }
     
     pub fn set_pluck_strength(&mut self, strength: f32) {
+        if !strength.is_finite() {
+            return;
+        }
         self.pluck_strength = strength.clamp(0.0, 1.0);
     }
     
     pub fn set_string_damping(&mut self, damping: f32) {
+        if !damping.is_finite() {
+            return;
+        }
         self.string_damping = damping.clamp(0.9, 0.999);
     }
     
-    fn calculate_frequency(&self, string_index: usize, fret: i32) -> f32 {
-        if string_index >= 6 || fret < 0 {
-            return 0.0;
+    // Damping for one string in place of the global value, e.g. higher on wound bass
+    // strings so they ring longer. A value of 0 or less goes back to the global damping.
+    pub fn set_string_damping_for(&mut self, string_index: usize, damping: f32) {
+        if !damping.is_finite() {
+            return;
+        }
+        if let Some(string_damping) = self.string_damping_overrides.get_mut(string_index) {
+            *string_damping = if damping > 0.0 { Some(damping.clamp(0.9, 0.999)) } else { None };
         }
-        
-        let base_freq = OPEN_STRING_FREQUENCIES[string_index];
-        base_freq * 2.0_f32.powf(fret as f32 / 12.0)
     }
     
-    fn generate_harmonics(&self, _fundamental: f32) -> Vec<f32> {
-        // Generate harmonic amplitudes for guitar-like timbre
-        vec![
-            0.5,   // 2nd harmonic
-            0.3,   // 3rd harmonic
-            0.2,   // 4th harmonic
-            0.15,  // 5th harmonic
-            0.1,   // 6th harmonic
-            0.05,  // 7th harmonic
-        ]
+    // Replaces the open-string frequencies (low to high). Ignored unless one positive
+    // frequency is given per string.
+    pub fn set_tuning(&mut self, frequencies: &[f32]) {
+        if frequencies.len() != self.open_string_frequencies.len() {
+            return;
+        }
+        if frequencies.iter().any(|f| !f.is_finite() || *f <= 0.0) {
+            return;
+        }
+        self.open_string_frequencies = frequencies.to_vec();
+        self.update_fret_frequencies();
     }
     
-    fn calculate_envelope(&self, elapsed: f32, attack: f32, release: f32, duration: f32) -> f32 {
-        if elapsed < attack {
-            // Attack phase
-            elapsed / attack
-        } else if elapsed < duration - release {
-            // Sustain phase
-            1.0
-        } else {
-            // Release phase
-            let release_elapsed = elapsed - (duration - release);
-            1.0 - (release_elapsed / release).min(1.0)
+    // Same as set_tuning but takes MIDI note numbers, e.g. [38, 45, 50, 55, 59, 64] for Drop D
+    pub fn set_tuning_from_notes(&mut self, midi_notes: &[i32]) {
+        let frequencies: Vec<f32> = midi_notes.iter().map(|&n| midi_to_frequency(n as f32, self.concert_pitch)).collect();
+        self.set_tuning(&frequencies);
+    }
+    
+    // Retunes every string by the given number of semitones (-48 to 48), so fret charts
+    // carry over to the new key. Notes already sounding keep their pitch.
+    pub fn transpose(&mut self, semitones: i32) {
+        if semitones == 0 {
+            return;
         }
+        let ratio = 2.0_f32.powf(semitones.clamp(-48, 48) as f32 / 12.0);
+        for frequency in self.open_string_frequencies.iter_mut() {
+            *frequency *= ratio;
+        }
+        self.update_fret_frequencies();
     }
     
-    fn soft_clip(&self, x: f32) -> f32 {
-        if x > 1.0 {
-            2.0 / 3.0
-        } else if x < -1.0 {
-            -2.0 / 3.0
-        } else {
-            x - (x * x * x) / 3.0
+    // Reference the instrument to another A4 (400-480 Hz), e.g. 442 for many orchestras
+    // or 415 for baroque pitch. The tuning is scaled by the ratio to the current
+    // reference, and note names, MIDI notes and set_tuning_from_notes follow it. Notes
+    // already sounding keep their pitch.
+    pub fn set_concert_pitch(&mut self, a4_hz: f32) {
+        if !a4_hz.is_finite() {
+            return;
+        }
+        let a4_hz = a4_hz.clamp(400.0, 480.0);
+        if a4_hz == self.concert_pitch {
+            return;
         }
+        let ratio = a4_hz / self.concert_pitch;
+        for frequency in self.open_string_frequencies.iter_mut().chain(self.default_tuning.iter_mut()) {
+            *frequency *= ratio;
+        }
+        self.concert_pitch = a4_hz;
+        self.update_fret_frequencies();
     }
-}
-
-// AudioWorklet processor implementation
-#[wasm_bindgen]
-pub struct GuitarAudioProcessor {
-    synthesizer: GuitarSynthesizer,
-}
-
-#[wasm_bindgen]
-impl GuitarAudioProcessor {
-    #[wasm_bindgen(constructor)]
-    pub fn new(sample_rate: f32) -> Self {
-        GuitarAudioProcessor {
-            synthesizer: GuitarSynthesizer::new(sample_rate),
+    
+    // Times in seconds; sustain is a level. Applies to notes played after the call.
+    // The defaults (see default_envelope) give the original attack/hold/release shape.
+    pub fn set_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        if !(attack.is_finite() && decay.is_finite() && sustain.is_finite() && release.is_finite()) {
+            return;
         }
+        self.attack_time = attack.clamp(0.0, 3.0);
+        self.decay_time = decay.clamp(0.0, 3.0);
+        self.sustain_level = sustain.clamp(0.0, 1.0);
+        self.release_time = release.clamp(0.001, 3.0);
     }
     
-    pub fn process_audio(&mut self, output: &mut [f32]) {
-        self.synthesizer.process(output);
+    // Shape of the attack and release ramps for notes played after the call:
+    // 0 = linear (default), 1 = exponential (the attack rises fast and eases into the
+    // peak, the release drops fast and tails off like a ringing string), 2 =
+    // logarithmic (the mirror image: slow to leave the start, quick to arrive).
+    // Unknown curves are ignored.
+    pub fn set_envelope_curve(&mut self, curve: u32) {
+        if let Some(curve) = EnvelopeCurve::from_u32(curve) {
+            self.envelope_curve = curve;
+        }
     }
     
-    pub fn play_chord(&mut self, fret_positions: &[i32]) {
-        self.synthesizer.play_chord(fret_positions);
+    // Softer plucks get slower attacks: a note's attack grows by up to
+    // VELOCITY_ATTACK_RANGE as its velocity falls, while full velocity keeps the
+    // envelope's own. Affects play_note_with_velocity and MIDI notes. Off by default.
+    pub fn set_velocity_attack(&mut self, enabled: bool) {
+        self.velocity_attack = enabled;
     }
     
-    pub fn play_note(&mut self, string_index: usize, fret: i32) {
-        self.synthesizer.play_note(string_index, fret);
+    // One-pole low-pass on the mixed voices. A cutoff at (or near) Nyquist bypasses it.
+    pub fn set_tone(&mut self, cutoff_hz: f32) {
+        if !cutoff_hz.is_finite() {
+            return;
+        }
+        let nyquist = self.sample_rate / 2.0;
+        self.tone_cutoff = cutoff_hz.clamp(20.0, nyquist);
+        self.tone_coefficient = if self.tone_cutoff >= nyquist * 0.99 {
+            1.0
+        } else {
+            1.0 - (-2.0 * PI * self.tone_cutoff / self.sample_rate).exp()
+        };
     }
     
-    pub fn stop_all(&mut self) {
-        self.synthesizer.stop_all();
+    // Three-band EQ after the tone control, gains in dB (+-24): a low shelf below 250 Hz,
+    // a broad peak at 1 kHz and a high shelf above 4 kHz. All three at 0 dB bypasses it.
+    pub fn set_eq(&mut self, low_gain: f32, mid_gain: f32, high_gain: f32) {
+        if !(low_gain.is_finite() && mid_gain.is_finite() && high_gain.is_finite()) {
+            return;
+        }
+        let [low, mid, high] = [low_gain, mid_gain, high_gain].map(|gain| gain.clamp(-24.0, 24.0));
+        let enabled = low != 0.0 || mid != 0.0 || high != 0.0;
+        if enabled && !self.eq_enabled {
+            self.eq_state.fill([[0.0; 2]; 3]);
+        }
+        self.eq_enabled = enabled;
+        self.eq_gains = [low, mid, high];
+        self.eq_coefficients = [
+            shelf_coefficients(self.sample_rate, EQ_LOW_FREQUENCY, low, false),
+            peaking_coefficients(self.sample_rate, EQ_MID_FREQUENCY, EQ_MID_Q, mid),
+            shelf_coefficients(self.sample_rate, EQ_HIGH_FREQUENCY, high, true),
+        ];
     }
     
-    pub fn set_pluck_strength(&mut self, strength: f32) {
-        self.synthesizer.set_pluck_strength(strength);
+    // Sinusoidal pitch modulation of every sounding note. A depth of 0 disables it.
+    pub fn set_vibrato(&mut self, depth_cents: f32, rate_hz: f32) {
+        if !(depth_cents.is_finite() && rate_hz.is_finite()) {
+            return;
+        }
+        self.vibrato_depth = depth_cents.clamp(0.0, 200.0);
+        self.vibrato_rate = rate_hz.clamp(0.0, 20.0);
     }
     
-    pub fn set_string_damping(&mut self, damping: f32) {
-        self.synthesizer.set_string_damping(damping);
+    // Pitch wheel: shifts every sounding note by the given number of semitones (+-24).
+    // The offset glides over a few milliseconds so wheel moves don't step audibly.
+    pub fn set_pitch_bend(&mut self, semitones: f32) {
+        if semitones.is_finite() {
+            self.pitch_bend_target = semitones.clamp(-MAX_PITCH_BEND, MAX_PITCH_BEND);
+        }
+    }
+    
+    // Whammy bar: glides every sounding note to the given offset in semitones over
+    // duration seconds, negative for a dive bomb. whammy_dive(0.0, t) lets the bar back
+    // up. Adds to the pitch wheel, with the two together limited to its +-24 range.
+    // A duration of 0 jumps straight there.
+    pub fn whammy_dive(&mut self, semitones: f32, duration: f32) {
+        if !semitones.is_finite() || !duration.is_finite() {
+            return;
+        }
+        self.whammy_target = semitones.clamp(-MAX_PITCH_BEND, MAX_PITCH_BEND);
+        if duration <= 0.0 {
+            self.whammy = self.whammy_target;
+        } else {
+            self.whammy_rate = (self.whammy_target - self.whammy).abs() / duration;
+        }
+    }
+    
+    // Amplitude modulation of the output. Depth 0.0 is off, 1.0 gates fully at the
+    // bottom of each LFO cycle. Independent of vibrato, so both can run together.
+    pub fn set_tremolo(&mut self, rate_hz: f32, depth: f32) {
+        if !(rate_hz.is_finite() && depth.is_finite()) {
+            return;
+        }
+        self.tremolo_rate = rate_hz.clamp(0.0, 20.0);
+        self.tremolo_depth = depth.clamp(0.0, 1.0);
+    }
+    
+    // Where the string is picked, as a fraction of its length: 0.0 at the bridge
+    // (brightest, the default) up to 0.5 over the 12th fret. Harmonic n is cancelled
+    // when n * position is a whole number, because the pluck point is one of its nodes:
+    //   0.5  (over the 12th fret) -> every even harmonic (2, 4, 6, ...), hollow and mellow
+    //   1/3  (over the 19th fret) -> harmonics 3, 6, 9, ...
+    //   0.25 (over the 24th fret) -> harmonics 4, 8, 12, ...
+    //   0.2                       -> harmonics 5, 10, ...
+    // Applies to notes played after the call.
+    pub fn set_pluck_position(&mut self, position: f32) {
+        if !position.is_finite() {
+            return;
+        }
+        self.pluck_position = position.clamp(0.0, 0.5);
+    }
+    
+    // How much faster the highs die away than the lows in Karplus-Strong mode (0.0-1.0),
+    // so notes darken as they ring. 0.0 keeps the plain loop average. Applies to notes
+    // played after the call.
+    pub fn set_brightness_decay(&mut self, amount: f32) {
+        if !amount.is_finite() {
+            return;
+        }
+        self.brightness_decay = amount.clamp(0.0, 1.0);
+    }
+    
+    // The harmonic profile a new synthesizer starts with, for a UI's reset button
+    pub fn default_harmonics() -> Vec<f32> {
+        DEFAULT_HARMONICS.to_vec()
+    }
+    
+    // Amplitudes of harmonics 2, 3, 4, ... relative to the fundamental, for notes played
+    // after the call (see DEFAULT_HARMONICS). Any length up to MAX_HARMONICS; empty gives
+    // a pure sine. Only heard in additive mode.
+    pub fn set_harmonic_profile(&mut self, amplitudes: &[f32]) {
+        if amplitudes.iter().any(|a| !a.is_finite()) {
+            return;
+        }
+        let count = amplitudes.len().min(MAX_HARMONICS);
+        self.harmonic_profile = amplitudes[..count].iter().map(|a| a.clamp(0.0, 1.0)).collect();
+    }
+    
+    // Timbre preset: 0 = steel (bright, long sustain), 1 = nylon (mellow, softer
+    // attack, shorter sustain). Sets the harmonic profile, attack time, damping and
+    // brightness decay, which can still be tweaked individually afterwards. Unknown
+    // materials are ignored.
+    pub fn set_string_material(&mut self, material: u32) {
+        let Some(material) = StringMaterial::from_u32(material) else {
+            return;
+        };
+        match material {
+            StringMaterial::Steel => {
+                self.set_harmonic_profile(&DEFAULT_HARMONICS);
+                self.attack_time = DEFAULT_ENVELOPE.0;
+                self.set_string_damping(0.997);
+                self.set_brightness_decay(0.0);
+            }
+            StringMaterial::Nylon => {
+                self.set_harmonic_profile(&NYLON_HARMONICS);
+                self.attack_time = 0.006;
+                self.set_string_damping(0.993);
+                self.set_brightness_decay(0.4);
+            }
+        }
+    }
+    
+    // How strongly untouched open strings ring in sympathy with played notes (0.0-1.0).
+    // 0.0 disables it.
+    pub fn set_sympathetic_resonance(&mut self, amount: f32) {
+        if !amount.is_finite() {
+            return;
+        }
+        self.sympathetic_resonance = amount.clamp(0.0, 1.0);
+    }
+    
+    // 12-string shimmer on the cheap: every played note gets a companion voice an octave
+    // up at amount times its level (0.0-1.0), slightly sharp and late. The companion
+    // shares the string, so it's released and retriggered with its parent, but it
+    // ignores bends and slides. 0.0 disables it.
+    pub fn set_octave_doubler(&mut self, amount: f32) {
+        if amount.is_finite() {
+            self.octave_doubler = amount.clamp(0.0, 1.0);
+        }
+    }
+    
+    // Thickens every played note into voices (1-8) detuned layers at the same pitch, the
+    // outermost detune_cents (up to 50) off, with the level shared so the stack is about
+    // as loud as one voice. The layers share the string like the octave doubler's
+    // companion, and never take more than the polyphony limit. 1 voice disables it.
+    pub fn set_unison(&mut self, voices: u32, detune_cents: f32) {
+        if !detune_cents.is_finite() {
+            return;
+        }
+        self.unison_voices = voices.clamp(1, MAX_UNISON_VOICES);
+        self.unison_detune = detune_cents.clamp(0.0, MAX_UNISON_DETUNE_CENTS);
+    }
+    
+    // Resonant band-pass on the voice mix, ahead of the soft clipper like a wah pedal in
+    // front of an amp. frequency is the pedal position (center Hz), resonance its Q.
+    // A mix of 0 bypasses it.
+    pub fn set_wah(&mut self, frequency: f32, resonance: f32, mix: f32) {
+        if !(frequency.is_finite() && resonance.is_finite() && mix.is_finite()) {
+            return;
+        }
+        self.wah_frequency = frequency.clamp(100.0, 5000.0);
+        self.wah_resonance = resonance.clamp(0.5, 20.0);
+        self.wah_mix = mix.clamp(0.0, 1.0);
+    }
+    
+    // Sweeps the wah center up and down by depth_octaves around its set_wah frequency at
+    // rate_hz, for hands-free wah. A rate or depth of 0 holds the pedal still.
+    pub fn set_auto_wah(&mut self, rate_hz: f32, depth_octaves: f32) {
+        if !(rate_hz.is_finite() && depth_octaves.is_finite()) {
+            return;
+        }
+        self.auto_wah_rate = rate_hz.clamp(0.0, 20.0);
+        self.auto_wah_depth = depth_octaves.clamp(0.0, 3.0);
+    }
+    
+    // Envelope filter: playing dynamics drive the wah instead of the pedal. The center
+    // rests at base_freq and sweeps up to range octaves (0-4) higher as the voice mix
+    // gets louder, reaching the top at a level of 1/sensitivity (sensitivity 0-10).
+    // Resonance and mix still come from set_wah, and set_auto_wah's sweep rides on top.
+    // A sensitivity of 0 hands the center back to set_wah's frequency.
+    pub fn set_envelope_wah(&mut self, sensitivity: f32, base_freq: f32, range: f32) {
+        if !(sensitivity.is_finite() && base_freq.is_finite() && range.is_finite()) {
+            return;
+        }
+        self.envelope_wah_sensitivity = sensitivity.clamp(0.0, 10.0);
+        self.envelope_wah_base = base_freq.clamp(100.0, 5000.0);
+        self.envelope_wah_range = range.clamp(0.0, 4.0);
+        let smoothing = |seconds: f32| (-1.0 / (seconds * self.sample_rate)).exp();
+        self.envelope_wah_attack = smoothing(ENVELOPE_WAH_ATTACK);
+        self.envelope_wah_release = smoothing(ENVELOPE_WAH_RELEASE);
+    }
+    
+    // Feed-forward peak compressor on the voice mix, ahead of the master gain. threshold
+    // is a linear level of the summed voices; above it the level rises only 1/ratio as
+    // fast. A ratio of 1.0 bypasses it.
+    pub fn set_compressor(&mut self, threshold: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        if !(threshold.is_finite() && ratio.is_finite() && attack_ms.is_finite() && release_ms.is_finite()) {
+            return;
+        }
+        self.compressor_threshold = threshold.clamp(0.001, 10.0);
+        self.compressor_ratio = ratio.clamp(1.0, 20.0);
+        let smoothing = |ms: f32| (-1.0 / (ms / 1000.0 * self.sample_rate)).exp();
+        self.compressor_attack = smoothing(attack_ms.clamp(0.1, 100.0));
+        self.compressor_release = smoothing(release_ms.clamp(1.0, 2000.0));
+    }
+    
+    // Silences the final output while it stays below threshold (linear, 0-1), so long
+    // tails and filter noise don't hum between notes. The gate opens at once on a louder
+    // sample and fades to exactly zero over release_ms (1-5000) once it has been quiet
+    // for NOISE_GATE_HOLD. A threshold of 0.0 disables it.
+    pub fn set_noise_gate(&mut self, threshold: f32, release_ms: f32) {
+        if !threshold.is_finite() || !release_ms.is_finite() {
+            return;
+        }
+        self.noise_gate_threshold = threshold.clamp(0.0, 1.0);
+        self.noise_gate_release = release_ms.clamp(1.0, 5000.0) / 1000.0;
+    }
+    
+    // Level of the voice mix going into the soft clipper (default 0.3), so higher values
+    // also push it into overdrive sooner
+    pub fn set_master_gain(&mut self, gain: f32) {
+        if !gain.is_finite() {
+            return;
+        }
+        self.master_gain = gain.clamp(0.0, 4.0);
+    }
+    
+    // Scales the voice mix by 1/sqrt of the voices sounding (unison layers and octave
+    // companions not counted), so a six-string chord ends up about as loud as a single
+    // note instead of six times louder. Off by default.
+    pub fn set_auto_gain(&mut self, enabled: bool) {
+        self.auto_gain = enabled;
+        if !enabled {
+            self.auto_gain_level = 1.0;
+        }
+    }
+    
+    // Rattle of the string against the frets at the start of plucks with velocity of
+    // FRET_BUZZ_VELOCITY or more. 0.0 (the default) keeps attacks clean.
+    pub fn set_fret_buzz(&mut self, amount: f32) {
+        if !amount.is_finite() {
+            return;
+        }
+        self.fret_buzz = amount.clamp(0.0, 1.0);
+    }
+    
+    // Level of the short noise click of the pick hitting the string at each note's start.
+    // 0.0 (the default) disables it.
+    pub fn set_pick_noise(&mut self, amount: f32) {
+        if !amount.is_finite() {
+            return;
+        }
+        self.pick_noise = amount.clamp(0.0, 1.0);
+    }
+    
+    // Input gain into the soft clipper; values above 1.0 overdrive it
+    pub fn set_drive(&mut self, drive: f32) {
+        if !drive.is_finite() {
+            return;
+        }
+        self.drive = drive.clamp(0.1, 20.0);
+    }
+    
+    // Runs the soft clipper at factor (1-8) times the sample rate so heavy drive doesn't
+    // alias. The CPU cost grows with the factor: each sample of each channel takes
+    // factor tanh calls and about 2 * OVERSAMPLING_TAPS * factor multiply-adds, where
+    // the clipper alone is one tanh. The filters also delay the output by about
+    // OVERSAMPLING_TAPS samples. 1 (the default) clips at the sample rate as before.
+    pub fn set_oversampling(&mut self, factor: u32) {
+        let factor = factor.clamp(1, MAX_OVERSAMPLING) as usize;
+        if factor == self.oversampling {
+            return;
+        }
+        self.oversampling = factor;
+        self.oversampling_kernel = if factor == 1 { Vec::new() } else { oversampling_kernel(factor) };
+        let oversampler = ClipOversampler::new(self.oversampling_kernel.len());
+        self.clip_oversamplers.iter_mut().for_each(|state| *state = oversampler.clone());
+    }
+    
+    // Feedback echo applied after the soft clipper. A mix of 0 bypasses it.
+    pub fn set_delay(&mut self, time_seconds: f32, feedback: f32, mix: f32) {
+        if !(time_seconds.is_finite() && feedback.is_finite() && mix.is_finite()) {
+            return;
+        }
+        let length = ((time_seconds.clamp(0.001, 2.0) * self.sample_rate) as usize).max(1);
+        if self.delay_buffers[0].len() != length {
+            self.delay_buffers.iter_mut().for_each(|buffer| *buffer = vec![0.0; length]);
+            self.delay_position = 0;
+            self.widen_feedback_lines();
+        }
+        self.delay_feedback = feedback.clamp(0.0, 0.95);
+        self.delay_mix = mix.clamp(0.0, 1.0);
+    }
+    
+    // Runs the delay and reverb feedback loops in f64, narrowing to f32 only where they
+    // join the output, so long held tails with heavy feedback don't pick up rounding
+    // noise. Costs about twice the delay and reverb memory and some speed, so f32 stays
+    // the default. Ringing tails carry over when it's switched.
+    pub fn set_high_precision(&mut self, enabled: bool) {
+        self.high_precision = enabled;
+        self.widen_feedback_lines();
+    }
+    
+    // Short modulated delay mixed with the dry signal. depth_ms (up to 10) is how far the
+    // delay sweeps; the right channel's sweep runs a quarter cycle ahead for width.
+    // A mix of 0 bypasses it.
+    pub fn set_chorus(&mut self, rate_hz: f32, depth_ms: f32, mix: f32) {
+        if !(rate_hz.is_finite() && depth_ms.is_finite() && mix.is_finite()) {
+            return;
+        }
+        if self.chorus_buffers[0].is_empty() {
+            let length = ((CHORUS_BASE_DELAY + CHORUS_MAX_DEPTH) * self.sample_rate) as usize + 2;
+            self.chorus_buffers.iter_mut().for_each(|buffer| *buffer = vec![0.0; length]);
+            self.chorus_position = 0;
+        }
+        self.chorus_rate = rate_hz.clamp(0.0, 10.0);
+        self.chorus_depth = (depth_ms / 1000.0).clamp(0.0, CHORUS_MAX_DEPTH);
+        self.chorus_mix = mix.clamp(0.0, 1.0);
+    }
+    
+    // Freeverb-style room: parallel damped combs into allpass diffusers, one tank per
+    // channel. room_size (0.0-1.0) sets the tail length, damping (0.0-1.0) how fast its
+    // highs die, and width (0.0-1.0) how far apart the two tanks are spread in
+    // process_stereo; process uses the left tank alone. A mix of 0 bypasses it.
+    pub fn set_reverb(&mut self, room_size: f32, damping: f32, mix: f32, width: f32) {
+        if !(room_size.is_finite() && damping.is_finite() && mix.is_finite() && width.is_finite()) {
+            return;
+        }
+        if self.reverb_combs[0].is_empty() {
+            let scale = self.sample_rate / 44100.0;
+            for channel in 0..2 {
+                let spread = channel * REVERB_STEREO_SPREAD;
+                self.reverb_combs[channel] = REVERB_COMB_TUNING.iter()
+                    .map(|&length| ReverbComb::new(((length + spread) as f32 * scale) as usize))
+                    .collect();
+                self.reverb_allpasses[channel] = REVERB_ALLPASS_TUNING.iter()
+                    .map(|&length| ReverbAllpass::new(((length + spread) as f32 * scale) as usize))
+                    .collect();
+            }
+            self.widen_feedback_lines();
+        }
+        self.reverb_feedback = 0.7 + 0.28 * room_size.clamp(0.0, 1.0);
+        self.reverb_damping = 0.4 * damping.clamp(0.0, 1.0);
+        self.reverb_mix = mix.clamp(0.0, 1.0);
+        self.reverb_width = width.clamp(0.0, 1.0);
+    }
+    
+    // Haas widening for process_stereo: the right channel lags the left by delay_ms
+    // (up to 40), which the ear hears as width rather than echo. 0 leaves the two in step.
+    pub fn set_stereo_width(&mut self, delay_ms: f32) {
+        if !delay_ms.is_finite() {
+            return;
+        }
+        let length = (delay_ms.clamp(0.0, 40.0) / 1000.0 * self.sample_rate) as usize;
+        if self.haas_buffer.len() != length {
+            self.haas_buffer = vec![0.0; length];
+            self.haas_position = 0;
+        }
+    }
+    
+    // Looper: capture what the synth plays (up to MAX_LOOP_SECONDS). The first pass
+    // sets the loop length when it stops; recording again overdubs onto the loop in
+    // time with its playback.
+    pub fn start_loop_recording(&mut self) {
+        if self.loop_length == 0 {
+            let capacity = (MAX_LOOP_SECONDS * self.sample_rate) as usize;
+            for buffer in self.loop_buffers.iter_mut() {
+                buffer.clear();
+                buffer.reserve(capacity);
+            }
+            self.loop_position = 0;
+        }
+        self.loop_recording = true;
+    }
+    
+    // Ends a recording or overdub pass. Closing the first pass restarts the loop from
+    // its top.
+    pub fn stop_loop_recording(&mut self) {
+        if self.loop_recording && self.loop_length == 0 {
+            self.loop_length = self.loop_buffers[0].len();
+            self.loop_position = 0;
+        }
+        self.loop_recording = false;
+    }
+    
+    // Mix the recorded loop into the output, repeating it until turned off
+    pub fn set_loop_playing(&mut self, playing: bool) {
+        self.loop_playing = playing;
+    }
+    
+    // Erase the loop and stop recording and playback, ready for a new first pass
+    pub fn clear_loop(&mut self) {
+        self.loop_buffers = [Vec::new(), Vec::new()];
+        self.loop_length = 0;
+        self.loop_position = 0;
+        self.loop_recording = false;
+        self.loop_playing = false;
+    }
+    
+    // Click track mixed into the output. Enabling it starts a new bar right away.
+    pub fn set_metronome(&mut self, bpm: f32, enabled: bool) {
+        if !bpm.is_finite() {
+            return;
+        }
+        self.metronome_bpm = bpm.clamp(20.0, 400.0);
+        if enabled && !self.metronome_enabled {
+            self.metronome_start = self.current_time;
+        }
+        self.metronome_enabled = enabled;
+    }
+    
+    // Beats per bar; the first beat of each bar gets the accented click
+    pub fn set_metronome_beats_per_bar(&mut self, beats_per_bar: u32) {
+        self.metronome_beats_per_bar = beats_per_bar.max(1);
+    }
+    
+    pub fn set_metronome_volume(&mut self, volume: f32) {
+        if !volume.is_finite() {
+            return;
+        }
+        self.metronome_volume = volume.clamp(0.0, 1.0);
+    }
+    
+    // Replays the last play_chord one string at a time, rate_hz steps per second (0.5-50).
+    // Each pattern entry is a string index into that chord, so [0, 2, 1, 3] picks the
+    // strings out of order; negative entries and strings the chord skips are rests. An
+    // empty pattern walks up the strings the chord plays. Enabling starts from the first
+    // step right away; disabling stops retriggering and lets the chord ring.
+    pub fn set_arpeggiator(&mut self, enabled: bool, rate_hz: f32, pattern: &[i32]) {
+        if rate_hz.is_finite() {
+            self.arpeggiator_rate = rate_hz.clamp(0.5, 50.0);
+        }
+        self.arpeggiator_pattern = pattern.to_vec();
+        if enabled && !self.arpeggiator_enabled {
+            self.arpeggiator_step = 0;
+            self.arpeggiator_next = self.current_time;
+        }
+        self.arpeggiator_enabled = enabled;
+    }
+    
+    // Adds the resonances of an acoustic body (see BODY_MODES) to the mixed strings.
+    // Off by default, which keeps the dry electric-style sound.
+    pub fn set_body_resonance(&mut self, enabled: bool) {
+        if enabled && !self.body_resonance_enabled {
+            self.body_state.fill([[0.0; 2]; BODY_MODES.len()]);
+        }
+        self.body_resonance_enabled = enabled;
+    }
+    
+    // High-pass around 20 Hz at the very end of the chain to strip DC offset. On by default.
+    pub fn set_dc_block(&mut self, enabled: bool) {
+        self.dc_block_enabled = enabled;
+    }
+    
+    // Reseeds the noise used to excite strings. Replaying the same notes after the same
+    // seed renders identical samples.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.noise_state = seed_noise_state(seed);
+    }
+    
+    // Clamps a capo at the given fret. Fret numbers passed to play_* are then relative
+    // to the capo, so fret 0 sounds at the capo position. set_capo(0) removes it. Frets
+    // past the end of the neck put the capo on the last one.
+    pub fn set_capo(&mut self, fret: i32) {
+        self.capo = fret.clamp(0, self.fret_count);
+    }
+    
+    // Clamps a capo at fret across only the listed strings (e.g. a cut capo on three of
+    // them), leaving the others open. Like set_capo, frets on a covered string are then
+    // relative to the capo. A partial capo lower than the full capo has no effect, and
+    // set_partial_capo(0, &[]) removes it. Unknown string indices are ignored, and frets
+    // past the end of the neck put it on the last one.
+    pub fn set_partial_capo(&mut self, fret: i32, strings: &[usize]) {
+        self.partial_capo = fret.clamp(0, self.fret_count);
+        self.partial_capo_strings.fill(false);
+        for &string_index in strings {
+            if let Some(covered) = self.partial_capo_strings.get_mut(string_index) {
+                *covered = true;
+            }
+        }
+    }
+    
+    // Frets on the neck (1-36), the range fingerings are validated against. Intonation
+    // offsets beyond the new last fret are dropped, and a capo past it moves onto it.
+    pub fn set_fret_count(&mut self, frets: u32) {
+        self.fret_count = frets.clamp(1, 36) as i32;
+        self.capo = self.capo.min(self.fret_count);
+        self.partial_capo = self.partial_capo.min(self.fret_count);
+        self.intonation_offsets.resize(self.fret_count as usize + 1, 0.0);
+        self.update_fret_frequencies();
+    }
+    
+    // Detunes one fret by up to +-100 cents from equal temperament, to model a poorly
+    // intonated or deliberately stretched neck. fret counts from the nut, so a capo
+    // doesn't move the offsets. Frets off the neck are ignored.
+    pub fn set_intonation_offset(&mut self, fret: i32, cents: f32) {
+        if !cents.is_finite() {
+            return;
+        }
+        if let Some(offset) = usize::try_from(fret).ok().and_then(|f| self.intonation_offsets.get_mut(f)) {
+            *offset = cents.clamp(-100.0, 100.0);
+            self.update_fret_frequencies();
+        }
+    }
+    
+    // Configuration as JSON, for presets: tuning, pluck strength, damping, tone, drive,
+    // envelope and capo. Sounding notes aren't included.
+    pub fn export_settings(&self) -> String {
+        let tuning: Vec<String> = self.open_string_frequencies.iter().map(|f| f.to_string()).collect();
+        format!(
+            "{{\"tuning\":[{}],\"pluck_strength\":{},\"string_damping\":{},\"tone_cutoff\":{},\"drive\":{},\"envelope\":{{\"attack\":{},\"decay\":{},\"sustain\":{},\"release\":{}}},\"capo\":{}}}",
+            tuning.join(","),
+            self.pluck_strength,
+            self.string_damping,
+            self.tone_cutoff,
+            self.drive,
+            self.attack_time,
+            self.decay_time,
+            self.sustain_level,
+            self.release_time,
+            self.capo
+        )
+    }
+    
+    // Restores settings written by export_settings. Missing, unknown or malformed fields
+    // fall back to the defaults, the tuning to the one the synthesizer was built with.
+    pub fn import_settings(&mut self, json: &str) {
+        let settings = json::parse(json).unwrap_or(JsonValue::Null);
+        let finite = |value: Option<&JsonValue>| value.and_then(JsonValue::as_f32).filter(|n| n.is_finite());
+        let number = |key: &str, default: f32| finite(settings.get(key)).unwrap_or(default);
+        let envelope = settings.get("envelope");
+        let envelope_number = |key: &str, default: f32| finite(envelope.and_then(|e| e.get(key))).unwrap_or(default);
+        
+        let tuning: Option<Vec<f32>> = settings
+            .get("tuning")
+            .and_then(JsonValue::as_array)
+            .and_then(|tuning| tuning.iter().map(JsonValue::as_f32).collect());
+        let default_tuning = self.default_tuning.clone();
+        self.set_tuning(&default_tuning);
+        if let Some(frequencies) = tuning {
+            self.set_tuning(&frequencies);
+        }
+        self.set_pluck_strength(number("pluck_strength", DEFAULT_PLUCK_STRENGTH));
+        self.set_string_damping(number("string_damping", DEFAULT_STRING_DAMPING));
+        self.set_tone(number("tone_cutoff", self.sample_rate / 2.0));
+        self.set_drive(number("drive", DEFAULT_DRIVE));
+        self.set_envelope(
+            envelope_number("attack", DEFAULT_ENVELOPE.0),
+            envelope_number("decay", DEFAULT_ENVELOPE.1),
+            envelope_number("sustain", DEFAULT_ENVELOPE.2),
+            envelope_number("release", DEFAULT_ENVELOPE.3),
+        );
+        self.set_capo(number("capo", 0.0) as i32);
+    }
+    
+    // Renders one block of voices, adding them into outputs: all of it into the one
+    // output for mono, panned across two for stereo, and with more each string into its
+    // set_string_channel output. Each voice runs through the whole block before the
+    // next, and the clock that drives pitch modulation is advanced up front, leaving
+    // each frame's time in times. Finished voices are dropped once, at the end.
+    fn mix_voices<B: AsMut<[f32]>>(&mut self, times: &mut [f32], outputs: &mut [B]) {
+        let mut pitch_ratios = [0.0; MIX_BLOCK];
+        for (time, pitch_ratio) in times.iter_mut().zip(pitch_ratios.iter_mut()) {
+            *time = self.current_time;
+            *pitch_ratio = self.vibrato_ratio() * self.pitch_bend_ratio();
+            self.advance_modulation();
+        }
+        
+        let sample_rate = self.sample_rate;
+        let nyquist = 0.5 * sample_rate;
+        let fret_buzz = self.fret_buzz;
+        let pick_samples = self.pick_noise_samples() as f32;
+        let pressure_step = 1.0 - (-1.0 / (PRESSURE_SMOOTHING * sample_rate)).exp();
+        for index in 0..self.active_notes.len() {
+            if !self.string_audible(self.active_notes[index].string_index) {
+                continue;
+            }
+            let damping = self.voice_damping(&self.active_notes[index]);
+            let stereo = outputs.len() == 2;
+            let (channel, (gain, right_gain)) = match outputs.len() {
+                1 => (0, (1.0, 0.0)),
+                2 => (0, self.pan_gains(&self.active_notes[index])),
+                count => (self.string_channel(self.active_notes[index].string_index, count), (1.0, 0.0)),
+            };
+            
+            // A NaN pitch would poison every filter downstream, so such voices are dropped
+            let note = &mut self.active_notes[index];
+            if !note.frequency.is_finite() {
+                continue;
+            }
+            
+            for (frame, &time) in times.iter().enumerate() {
+                let elapsed = time - note.start_time;
+                if elapsed > note.duration {
+                    break;
+                }
+                
+                // Scheduled later in a strum: silent and frozen until its start time
+                if elapsed < 0.0 {
+                    continue;
+                }
+                
+                let envelope = note.envelope(elapsed);
+                let mut signal = match note.mode {
+                    SynthesisMode::KarplusStrong => note.next_string_sample(damping) * envelope * note.amplitude,
+                    SynthesisMode::Additive => {
+                        // Fundamental plus harmonics for a more realistic guitar sound,
+                        // band-limited to below Nyquist (see ANTI_ALIAS_TAPER)
+                        let frequency = note.frequency * pitch_ratios[frame];
+                        let mut signal = (note.phase * 2.0 * PI).sin() * note.amplitude;
+                        for (harmonic_index, &harmonic_amp) in note.harmonics.iter().enumerate() {
+                            let number = (harmonic_index + 2) as f32;
+                            let headroom = (nyquist - number * frequency) / (ANTI_ALIAS_TAPER * nyquist);
+                            if headroom <= 0.0 {
+                                break;
+                            }
+                            let level = harmonic_amp * headroom.min(1.0);
+                            signal += (note.phase * number * 2.0 * PI).sin() * level * note.amplitude;
+                        }
+                        signal * envelope * note.amplitude
+                    }
+                };
+                if note.pressure != 0.0 || note.pressure_target != 0.0 {
+                    signal = note.apply_pressure(signal, pressure_step);
+                }
+                if note.buzz_remaining > 0.0 {
+                    let weight = fret_buzz * note.buzz_remaining / FRET_BUZZ_DURATION;
+                    let clipped = signal.min(FRET_BUZZ_CLIP * note.amplitude);
+                    note.buzz_filter += 0.3 * (xorshift_noise(&mut self.noise_state) - note.buzz_filter);
+                    signal += weight * (clipped - signal + FRET_BUZZ_NOISE * note.amplitude * note.buzz_filter);
+                    note.buzz_remaining -= 1.0 / sample_rate;
+                }
+                if note.click_remaining > 0 {
+                    let fade = note.click_remaining as f32 / pick_samples;
+                    signal += note.click_level * note.amplitude * fade * xorshift_noise(&mut self.noise_state);
+                    note.click_remaining -= 1;
+                }
+                outputs[channel].as_mut()[frame] += signal * gain;
+                if stereo {
+                    outputs[1].as_mut()[frame] += signal * right_gain;
+                }
+                
+                note.update_bend(sample_rate);
+                if note.update_slide(sample_rate) {
+                    note.click_remaining = pick_samples as usize;
+                    note.click_level = SLIDE_FRET_NOISE;
+                }
+                let frequency = note.frequency * pitch_ratios[frame];
+                if note.mode == SynthesisMode::KarplusStrong {
+                    note.retune_string(sample_rate, frequency);
+                }
+                note.phase += frequency / sample_rate;
+                if note.phase > 1.0 {
+                    note.phase -= 1.0;
+                }
+                
+                // Apply string damping (the Karplus-Strong loop decays on its own)
+                if note.mode == SynthesisMode::Additive {
+                    note.amplitude *= damping;
+                }
+            }
+        }
+        
+        if self.auto_gain {
+            self.apply_auto_gain(times, outputs);
+        }
+        if let Some(&last_time) = times.last() {
+            self.active_notes.retain(|note| last_time - note.start_time <= note.duration && note.frequency.is_finite());
+        }
+    }
+    
+    // Set auto_gain_level to 1/sqrt of the voices sounding at each frame and scale the
+    // mixed block by it. New notes cut the level at once so a chord's onset isn't loud,
+    // and it recovers smoothly as they end.
+    fn apply_auto_gain<B: AsMut<[f32]>>(&mut self, times: &[f32], outputs: &mut [B]) {
+        let step = 1.0 - (-1.0 / (AUTO_GAIN_SMOOTHING * self.sample_rate)).exp();
+        for (frame, &time) in times.iter().enumerate() {
+            let sounding = self
+                .active_notes
+                .iter()
+                .filter(|n| !n.companion && time >= n.start_time && time - n.start_time <= n.duration)
+                .count();
+            let target = 1.0 / (sounding.max(1) as f32).sqrt();
+            if target < self.auto_gain_level {
+                self.auto_gain_level = target;
+            } else {
+                self.auto_gain_level += (target - self.auto_gain_level) * step;
+            }
+            for output in outputs.iter_mut() {
+                output.as_mut()[frame] *= self.auto_gain_level;
+            }
+        }
+    }
+    
+    // Move scheduled notes whose start time has come into the active voices
+    fn start_scheduled_notes(&mut self) {
+        while self.scheduled_notes.front().is_some_and(|n| n.start_time <= self.current_time) {
+            let Some(scheduled) = self.scheduled_notes.pop_front() else {
+                break;
+            };
+            // Keep the exact start time, unless it passed more than a frame ago
+            let mut note = self.fretted_note(scheduled.string_index, scheduled.fret, 1.0);
+            if scheduled.start_time > self.current_time - 1.0 / self.sample_rate {
+                note.start_time = scheduled.start_time;
+            }
+            note.set_length(scheduled.duration);
+            self.start_note(note);
+        }
+    }
+    
+    // Retrigger the held chord's strings for every arpeggio step whose time has come
+    fn advance_arpeggiator(&mut self) {
+        if !self.arpeggiator_enabled {
+            return;
+        }
+        while self.arpeggiator_next <= self.current_time {
+            let start_time = self.arpeggiator_next;
+            self.arpeggiator_next += 1.0 / self.arpeggiator_rate;
+            let Some((string_index, fret)) = self.next_arpeggio_string() else {
+                continue;
+            };
+            let mut note = self.fretted_note(string_index, fret, 1.0);
+            if start_time > self.current_time - 1.0 / self.sample_rate {
+                note.start_time = start_time;
+            }
+            note.set_length(DEFAULT_NOTE_DURATION);
+            self.start_note(note);
+        }
+    }
+    
+    // String and fret for the next pattern step, or None for a rest
+    fn next_arpeggio_string(&mut self) -> Option<(usize, i32)> {
+        let step = self.arpeggiator_step;
+        self.arpeggiator_step = self.arpeggiator_step.wrapping_add(1);
+        let string_index = if self.arpeggiator_pattern.is_empty() {
+            let mut played = self.held_chord.iter().enumerate().filter(|(_, &fret)| fret >= 0);
+            let count = played.clone().count();
+            if count == 0 {
+                return None;
+            }
+            played.nth(step % count)?.0
+        } else {
+            let index = self.arpeggiator_pattern[step % self.arpeggiator_pattern.len()];
+            usize::try_from(index).ok()?
+        };
+        let fret = *self.held_chord.get(string_index)?;
+        self.validate_fingering(string_index, fret).ok()?;
+        Some((string_index, fret))
+    }
+    
+    // Frames to render before the next scheduled note or arpeggio step is due, so it
+    // starts on its sample
+    fn block_length(&self, remaining: usize) -> usize {
+        let mut frames = remaining.min(MIX_BLOCK);
+        if let Some(next) = self.scheduled_notes.front() {
+            let until_start = ((next.start_time - self.current_time) * self.sample_rate).ceil();
+            frames = frames.min((until_start as usize).max(1));
+        }
+        if self.arpeggiator_enabled {
+            let until_step = ((self.arpeggiator_next - self.current_time) * self.sample_rate).ceil();
+            frames = frames.min((until_step as usize).max(1));
+        }
+        frames
+    }
+    
+    fn record_history(&mut self, sample: f32) {
+        self.output_history[self.history_position] = sample;
+        self.history_position = (self.history_position + 1) % SPECTRUM_HISTORY;
+    }
+    
+    // process_multi for three or more equally long channels
+    fn process_channels(&mut self, channels: &mut [&mut [f32]]) {
+        let channel_count = channels.len();
+        self.ensure_channels(channel_count);
+        let mut mixes = std::mem::take(&mut self.channel_mixes);
+        let mut times = [0.0; MIX_BLOCK];
+        let mut peak: f32 = 0.0;
+        let mut sum_of_squares = 0.0;
+        let total_frames = channels[0].len();
+        let mut offset = 0;
+        while offset < total_frames {
+            self.start_scheduled_notes();
+            self.advance_arpeggiator();
+            let frames = self.block_length(total_frames - offset);
+            
+            for mix in mixes.iter_mut() {
+                mix[..frames].fill(0.0);
+            }
+            self.mix_voices(&mut times[..frames], &mut mixes);
+            for frame in 0..frames {
+                let mut loudest: f32 = 0.0;
+                for (channel, output) in channels.iter_mut().enumerate() {
+                    let sample = self.finish_sample(channel, mixes[channel][frame], times[frame]);
+                    output[offset + frame] = sample;
+                    loudest = loudest.max(sample.abs());
+                }
+                let gain = self.noise_gate_gain(loudest);
+                let mut sum = 0.0;
+                for output in channels.iter_mut() {
+                    let sample = &mut output[offset + frame];
+                    *sample *= gain;
+                    sum += *sample;
+                    peak = peak.max(sample.abs());
+                    sum_of_squares += *sample * *sample;
+                }
+                self.record_history(sum / channel_count as f32);
+                self.advance_effects();
+            }
+            offset += frames;
+        }
+        self.channel_mixes = mixes;
+        self.update_levels(peak, sum_of_squares, total_frames * channel_count);
+    }
+    
+    // Bring every delay and reverb line's f64 storage in line with high_precision
+    fn widen_feedback_lines(&mut self) {
+        let enabled = self.high_precision;
+        for (narrow, wide) in self.delay_buffers.iter_mut().zip(self.delay_wide_buffers.iter_mut()) {
+            widen_line(narrow, wide, enabled);
+        }
+        self.reverb_combs.iter_mut().flatten().for_each(|comb| comb.set_wide(enabled));
+        for allpass in self.reverb_allpasses.iter_mut().flatten() {
+            widen_line(&mut allpass.buffer, &mut allpass.wide_buffer, enabled);
+        }
+    }
+    
+    // Grow the per-channel effect state to cover count channels, and size the voice
+    // mix buffers to exactly count
+    fn ensure_channels(&mut self, count: usize) {
+        if self.tone_state.len() < count {
+            self.tone_state.resize(count, 0.0);
+            self.eq_state.resize(count, [[0.0; 2]; 3]);
+            self.body_state.resize(count, [[0.0; 2]; BODY_MODES.len()]);
+            self.compressor_envelope.resize(count, 0.0);
+            self.wah_state.resize(count, [0.0; 2]);
+            self.envelope_wah_level.resize(count, 0.0);
+            let chorus_length = self.chorus_buffers[0].len();
+            self.chorus_buffers.resize(count, vec![0.0; chorus_length]);
+            let delay_length = self.delay_buffers[0].len();
+            self.delay_buffers.resize(count, vec![0.0; delay_length]);
+            self.delay_wide_buffers.resize(count, Vec::new());
+            self.widen_feedback_lines();
+            self.dc_block_input.resize(count, 0.0);
+            self.dc_block_output.resize(count, 0.0);
+            let oversampler = ClipOversampler::new(self.oversampling_kernel.len());
+            self.clip_oversamplers.resize(count, oversampler);
+        }
+        self.channel_mixes.resize(count, [0.0; MIX_BLOCK]);
+    }
+    
+    fn update_levels(&mut self, peak: f32, sum_of_squares: f32, samples: usize) {
+        self.last_buffer_peak = peak;
+        self.last_buffer_rms = if samples == 0 { 0.0 } else { (sum_of_squares / samples as f32).sqrt() };
+    }
+    
+    // Effects chain for one frame of one channel; time is the frame's clock
+    fn finish_sample(&mut self, channel: usize, mix: f32, time: f32) -> f32 {
+        let output = self.apply_tone(channel, mix);
+        let output = self.apply_eq(channel, output);
+        let output = self.apply_body_resonance(channel, output);
+        let output = self.apply_compressor(channel, output);
+        let output = self.apply_wah(channel, output);
+        
+        // Soft clipping to prevent distortion
+        let output = self.apply_soft_clip(channel, output * self.master_gain);
+        let output = output * self.tremolo_gain();
+        let output = self.apply_chorus(channel, output);
+        
+        let output = self.apply_delay(channel, output);
+        
+        let output = if self.metronome_enabled {
+            output + self.metronome_click(time)
+        } else {
+            output
+        };
+        
+        self.apply_dc_block(channel, output)
+    }
+    
+    // Clock, vibrato and pitch wheel: everything the voices read
+    fn advance_modulation(&mut self) {
+        self.vibrato_phase = (self.vibrato_phase + self.vibrato_rate / self.sample_rate).fract();
+        self.current_time += 1.0 / self.sample_rate;
+        
+        if self.pitch_bend != self.pitch_bend_target {
+            let step = 1.0 - (-1.0 / (PITCH_BEND_SMOOTHING * self.sample_rate)).exp();
+            self.pitch_bend += (self.pitch_bend_target - self.pitch_bend) * step;
+            if (self.pitch_bend_target - self.pitch_bend).abs() < 1e-4 {
+                self.pitch_bend = self.pitch_bend_target;
+            }
+        }
+        
+        if self.whammy != self.whammy_target {
+            let step = self.whammy_rate / self.sample_rate;
+            let remaining = self.whammy_target - self.whammy;
+            self.whammy = if remaining.abs() <= step {
+                self.whammy_target
+            } else {
+                self.whammy + step * remaining.signum()
+            };
+        }
+    }
+    
+    // Per-frame state of the output effects
+    fn advance_effects(&mut self) {
+        self.tremolo_phase = (self.tremolo_phase + self.tremolo_rate / self.sample_rate).fract();
+        self.auto_wah_phase = (self.auto_wah_phase + self.auto_wah_rate / self.sample_rate).fract();
+        if !self.delay_buffers[0].is_empty() {
+            self.delay_position = (self.delay_position + 1) % self.delay_buffers[0].len();
+        }
+        if !self.chorus_buffers[0].is_empty() {
+            self.chorus_phase = (self.chorus_phase + self.chorus_rate / self.sample_rate).fract();
+            self.chorus_position = (self.chorus_position + 1) % self.chorus_buffers[0].len();
+        }
+    }
+    
+    fn create_note(&self, string_index: usize, frequency: f32) -> GuitarNote {
+        let mut note = GuitarNote {
+            frequency,
+            amplitude: self.pluck_strength,
+            phase: 0.0,
+            attack_time: self.attack_time,
+            decay_time: self.decay_time,
+            sustain_level: self.sustain_level,
+            release_time: self.release_time,
+            fade_level: None,
+            envelope_curve: self.envelope_curve,
+            start_time: self.current_time,
+            duration: DEFAULT_NOTE_DURATION,
+            harmonics: Vec::new(),
+            string_index,
+            velocity: 1.0,
+            palm_mute: false,
+            flageolet: false,
+            midi_note: None,
+            pending_release: false,
+            buzz_remaining: 0.0,
+            buzz_filter: 0.0,
+            click_remaining: 0,
+            click_level: 0.0,
+            slide_frets_left: 0,
+            slide_ratio: 1.0,
+            slide_fret_time: 0.0,
+            slide_clock: 0.0,
+            pressure: 0.0,
+            pressure_target: 0.0,
+            pressure_previous: 0.0,
+            companion: false,
+            bend_target: frequency,
+            bend_rate: 0.0,
+            mode: self.synthesis_mode,
+            delay_line: Vec::new(),
+            resample_buffer: Vec::new(),
+            delay_index: 0,
+            loop_frequency: frequency,
+            allpass_coefficient: 0.0,
+            allpass_input: 0.0,
+            allpass_output: 0.0,
+            loop_lowpass: self.brightness_decay * MAX_LOOP_LOWPASS,
+            loop_lowpass_state: 0.0,
+        };
+        note.harmonics = self.generate_harmonics(&note);
+        note
+    }
+    
+    // Notes that haven't started are cancelled and the rest enter their release, or with
+    // the sustain pedal down are only marked for release
+    fn release_voices(&mut self, matches: impl Fn(&GuitarNote) -> bool) {
+        if self.sustain_pedal {
+            for note in self.active_notes.iter_mut().filter(|n| matches(n)) {
+                note.pending_release = true;
+            }
+            return;
+        }
+        
+        let current_time = self.current_time;
+        self.active_notes.retain(|n| !matches(n) || current_time >= n.start_time);
+        for note in self.active_notes.iter_mut().filter(|n| matches(n)) {
+            note.pending_release = false;
+            note.release(current_time - note.start_time);
+        }
+    }
+    
+    fn pick_noise_samples(&self) -> usize {
+        ((PICK_NOISE_DURATION * self.sample_rate) as usize).max(1)
+    }
+    
+    // Deadened string: a palm-mute-dark burst at the open pitch that dies almost at once
+    fn muted_strum_note(&self, string_index: usize) -> GuitarNote {
+        let frequency = self.calculate_frequency(string_index, 0);
+        let mut note = self.create_note(string_index, frequency);
+        note.palm_mute = true;
+        note.amplitude *= MUTED_STRUM_LEVEL;
+        note.set_length(MUTED_STRUM_DURATION);
+        note.harmonics = self.generate_harmonics(&note);
+        note
+    }
+    
+    fn fretted_note(&self, string_index: usize, fret: i32, velocity: f32) -> GuitarNote {
+        let frequency = self.calculate_frequency(string_index, fret);
+        let mut note = self.create_note(string_index, frequency);
+        note.velocity = velocity.clamp(0.0, 1.0);
+        note.amplitude *= note.velocity;
+        if self.velocity_attack {
+            note.attack_time += (1.0 - note.velocity) * VELOCITY_ATTACK_RANGE;
+        }
+        note.harmonics = self.generate_harmonics(&note);
+        note
+    }
+    
+    fn start_note(&mut self, mut note: GuitarNote) {
+        // A slow attack such as a swell hides the pick, so no onset transients
+        let audible_pick = note.attack_time <= SWELL_MIN_ATTACK;
+        if audible_pick && self.fret_buzz > 0.0 && note.velocity >= FRET_BUZZ_VELOCITY {
+            note.buzz_remaining = FRET_BUZZ_DURATION;
+        }
+        if audible_pick && self.pick_noise > 0.0 {
+            note.click_remaining = self.pick_noise_samples();
+            note.click_level = self.pick_noise;
+        }
+        let frequency = note.frequency;
+        let string_index = note.string_index;
+        let companion = (self.octave_doubler > 0.0).then(|| self.octave_companion(&note));
+        let layers = self.unison_layers(&mut note);
+        self.push_voice(note);
+        for layer in layers {
+            self.push_voice(layer);
+        }
+        if let Some(companion) = companion {
+            self.push_voice(companion);
+        }
+        
+        if self.sympathetic_resonance > 0.0 {
+            self.excite_sympathetic_strings(string_index, frequency);
+        }
+    }
+    
+    fn push_voice(&mut self, mut note: GuitarNote) {
+        if note.mode == SynthesisMode::KarplusStrong {
+            self.excite_string(&mut note);
+        }
+        
+        // Remove any existing note on the same string, which a companion has just joined
+        if note.string_index != FREE_VOICE && !note.companion {
+            if self.smooth_retrigger {
+                self.fade_out_string(note.string_index);
+            } else {
+                self.active_notes.retain(|n| n.string_index != note.string_index);
+            }
+        }
+        
+        while self.active_notes.len() >= self.max_voices {
+            self.steal_voice();
+        }
+        self.active_notes.push(note);
+    }
+    
+    // Copy of a note about to start, an octave up for the octave doubler
+    fn octave_companion(&self, note: &GuitarNote) -> GuitarNote {
+        let mut companion = note.clone();
+        companion.frequency *= 2.0 * 2.0_f32.powf(OCTAVE_DOUBLER_DETUNE_CENTS / 1200.0);
+        companion.bend_target = companion.frequency;
+        companion.amplitude *= self.octave_doubler;
+        companion.start_time += OCTAVE_DOUBLER_DELAY;
+        companion.companion = true;
+        companion
+    }
+    
+    // Detuned copies of a note about to start for unison, which also lowers the note's
+    // own level to its share of the stack. Layers alternate sharp and flat, widening
+    // out to unison_detune.
+    fn unison_layers(&self, note: &mut GuitarNote) -> Vec<GuitarNote> {
+        let voices = (self.unison_voices as usize).min(self.max_voices);
+        if voices <= 1 {
+            return Vec::new();
+        }
+        note.amplitude /= (voices as f32).sqrt();
+        
+        let widest = (voices / 2) as f32;
+        (1..voices)
+            .map(|layer| {
+                let sign = if layer % 2 == 1 { 1.0 } else { -1.0 };
+                let cents = sign * self.unison_detune * layer.div_ceil(2) as f32 / widest;
+                let mut copy = note.clone();
+                copy.frequency *= 2.0_f32.powf(cents / 1200.0);
+                copy.bend_target = copy.frequency;
+                copy.companion = true;
+                copy
+            })
+            .collect()
+    }
+    
+    // Detach the string's sounding note into a free voice that releases over the
+    // retrigger choke from its current level. Notes that haven't started are dropped.
+    fn fade_out_string(&mut self, string_index: usize) {
+        let current_time = self.current_time;
+        self.active_notes.retain(|n| n.string_index != string_index || n.start_time <= current_time);
+        for note in self.active_notes.iter_mut().filter(|n| n.string_index == string_index) {
+            note.fade_out(current_time - note.start_time, self.retrigger_fade);
+            note.string_index = FREE_VOICE;
+        }
+    }
+    
+    // Let silent open strings ring along when their pitch is a whole-number multiple or
+    // division of the played frequency (within SYMPATHETIC_TOLERANCE_CENTS)
+    fn excite_sympathetic_strings(&mut self, source_string: usize, frequency: f32) {
+        if frequency <= 0.0 {
+            return;
+        }
+        
+        for string_index in 0..self.open_string_frequencies.len() {
+            if string_index == source_string || self.active_notes.iter().any(|n| n.string_index == string_index) {
+                continue;
+            }
+            
+            let open_frequency = self.calculate_frequency(string_index, 0);
+            let ratio = if open_frequency > frequency {
+                open_frequency / frequency
+            } else {
+                frequency / open_frequency
+            };
+            let harmonic = ratio.round();
+            if harmonic > SYMPATHETIC_MAX_HARMONIC {
+                continue;
+            }
+            let cents = 1200.0 * (ratio / harmonic).log2();
+            if cents.abs() > SYMPATHETIC_TOLERANCE_CENTS {
+                continue;
+            }
+            
+            let mut note = self.create_note(string_index, open_frequency);
+            note.amplitude *= self.sympathetic_resonance * SYMPATHETIC_LEVEL / harmonic;
+            self.push_voice(note);
+        }
+    }
+    
+    // Drop the quietest voice, preferring the oldest when levels tie
+    fn steal_voice(&mut self) {
+        let level = |note: &GuitarNote| {
+            let elapsed = self.current_time - note.start_time;
+            if elapsed < 0.0 {
+                // Not started yet; about to be heard at full level
+                return note.amplitude;
+            }
+            note.amplitude * note.envelope(elapsed)
+        };
+        let quietest = self
+            .active_notes
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                level(a)
+                    .total_cmp(&level(b))
+                    .then(a.start_time.total_cmp(&b.start_time))
+            })
+            .map(|(index, _)| index);
+        if let Some(index) = quietest {
+            self.active_notes.remove(index);
+        }
+    }
+    
+    // In legato mode, move the string's ringing note (if it isn't releasing yet) to the
+    // new fret and let it ring for duration from now. false if a new note is needed.
+    fn legato_transition(&mut self, string_index: usize, fret: i32, duration: f32) -> bool {
+        if !self.legato {
+            return false;
+        }
+        let current_time = self.current_time;
+        let Some(note) = self.active_notes.iter_mut().find(|n| {
+            let elapsed = current_time - n.start_time;
+            n.string_index == string_index && elapsed >= 0.0 && elapsed < n.duration - n.release_time
+        }) else {
+            return false;
+        };
+        
+        note.set_length(current_time - note.start_time + duration);
+        self.change_fret(string_index, fret, 1.0);
+        true
+    }
+    
+    // Move the ringing note on a string to a new fret without re-triggering its envelope
+    fn change_fret(&mut self, string_index: usize, new_fret: i32, amplitude_scale: f32) {
+        let frequency = self.calculate_frequency(string_index, new_fret);
+        if frequency <= 0.0 {
+            return;
+        }
+        
+        let Some(index) = self.active_notes.iter().position(|n| n.string_index == string_index) else {
+            return;
+        };
+        let note = &mut self.active_notes[index];
+        note.frequency = frequency;
+        note.bend_target = frequency;
+        note.bend_rate = 0.0;
+        note.amplitude = (note.amplitude * amplitude_scale).min(1.0);
+        self.active_notes[index].harmonics = self.generate_harmonics(&self.active_notes[index]);
+    }
+    
+    fn voice_damping(&self, note: &GuitarNote) -> f32 {
+        let damping = self
+            .string_damping_overrides
+            .get(note.string_index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.string_damping);
+        if note.palm_mute {
+            damping * PALM_MUTE_DAMPING
+        } else {
+            damping
+        }
+    }
+    
+    // Size the delay line to one period of the note and fill it with a noise burst
+    fn excite_string(&mut self, note: &mut GuitarNote) {
+        if note.frequency <= 0.0 || !note.frequency.is_finite() {
+            return;
+        }
+        
+        let (length, allpass_coefficient) = string_loop_tuning(self.sample_rate, note.frequency, note.loop_lowpass);
+        note.loop_frequency = note.frequency;
+        note.allpass_coefficient = allpass_coefficient;
+        
+        let mut delay_line = Vec::with_capacity(length * LOOP_HEADROOM);
+        delay_line.extend((0..length).map(|_| self.next_noise()));
+        
+        // Darker excitation for soft plucks, and when the palm soaks up the high end
+        let mut smoothing = 0.5 + 0.5 * note.velocity;
+        if note.palm_mute {
+            smoothing *= 0.3;
+        }
+        if note.flageolet {
+            smoothing *= 0.2;
+        }
+        if smoothing < 1.0 {
+            let mut smoothed = 0.0;
+            for sample in delay_line.iter_mut() {
+                smoothed += smoothing * (*sample - smoothed);
+                *sample = smoothed;
+            }
+        }
+        
+        if self.pluck_position > 0.0 {
+            // Feed-forward comb: subtracting the excitation delayed by the pluck point
+            // cancels the harmonics that have a node there
+            let offset = ((self.pluck_position * length as f32).round() as usize).max(1);
+            let excitation = delay_line.clone();
+            for (i, sample) in delay_line.iter_mut().enumerate() {
+                *sample = excitation[i] - excitation[(i + length - offset) % length];
+            }
+        }
+        
+        let mean = delay_line.iter().sum::<f32>() / length as f32;
+        for sample in delay_line.iter_mut() {
+            *sample -= mean;
+        }
+        note.delay_line = delay_line;
+        note.resample_buffer = Vec::with_capacity(length * LOOP_HEADROOM);
+    }
+    
+    fn next_noise(&mut self) -> f32 {
+        xorshift_noise(&mut self.noise_state)
+    }
+    
+    // fret_position_for_frequency limited to the strings usable accepts
+    fn fret_position_among(&self, freq: f32, usable: impl Fn(usize) -> bool) -> Option<(usize, i32)> {
+        if !freq.is_finite() || freq <= 0.0 {
+            return None;
+        }
+        
+        let mut best: Option<(usize, i32, f32)> = None;
+        for string_index in (0..self.open_string_frequencies.len()).filter(|&s| usable(s)) {
+            let open = self.calculate_frequency(string_index, 0);
+            let semitones = 12.0 * (freq / open).log2();
+            let fret = semitones.round() as i32;
+            let error = (semitones - fret as f32).abs();
+            let highest_fret = self.fret_count - self.string_capo(string_index);
+            if fret < 0 || fret > highest_fret || error > FRET_POSITION_TOLERANCE_CENTS / 100.0 {
+                continue;
+            }
+            
+            let better = match best {
+                None => true,
+                Some((_, best_fret, best_error)) => fret < best_fret || (fret == best_fret && error < best_error),
+            };
+            if better {
+                best = Some((string_index, fret, error));
+            }
+        }
+        best.map(|(string_index, fret, _)| (string_index, fret))
+    }
+    
+    // Whether the string has a voice that hasn't started its release
+    fn string_sounding(&self, string_index: usize) -> bool {
+        self.active_notes
+            .iter()
+            .any(|n| n.string_index == string_index && n.envelope_stage(self.current_time - n.start_time) != "release")
+    }
+    
+    fn validate_fingering(&self, string_index: usize, fret: i32) -> Result<(), String> {
+        let string_count = self.open_string_frequencies.len();
+        if string_index >= string_count {
+            return Err(format!("string {} out of range (0-{})", string_index, string_count - 1));
+        }
+        let highest_fret = self.fret_count - self.string_capo(string_index);
+        if !(0..=highest_fret).contains(&fret) {
+            return Err(format!("fret {} out of range (0-{})", fret, highest_fret));
+        }
+        Ok(())
+    }
+    
+    fn update_fret_frequencies(&mut self) {
+        self.fret_frequencies = fret_frequency_table(&self.open_string_frequencies, &self.intonation_offsets);
+    }
+    
+    // Fret the string is clamped at by the capo and partial capo
+    fn string_capo(&self, string_index: usize) -> i32 {
+        if self.partial_capo_strings.get(string_index) == Some(&true) {
+            self.capo.max(self.partial_capo)
+        } else {
+            self.capo
+        }
+    }
+    
+    fn calculate_frequency(&self, string_index: usize, fret: i32) -> f32 {
+        if string_index >= self.open_string_frequencies.len() || fret < 0 {
+            return 0.0;
+        }
+        
+        let neck_fret = fret.saturating_add(self.string_capo(string_index)) as usize;
+        let row_length = self.intonation_offsets.len();
+        if neck_fret < row_length {
+            return self.fret_frequencies[string_index * row_length + neck_fret];
+        }
+        
+        // Past the last fret, where there is no intonation offset either
+        self.open_string_frequencies[string_index] * 2.0_f32.powf(neck_fret as f32 / 12.0)
+    }
+    
+    fn generate_harmonics(&self, note: &GuitarNote) -> Vec<f32> {
+        // Flageolets are close to a sine: just a trace of the next two partials
+        if note.flageolet {
+            return vec![0.08, 0.02, 0.0, 0.0, 0.0, 0.0];
+        }
+        
+        // Harmonic amplitudes for guitar-like timbre
+        let harmonics = self.harmonic_profile.clone();
+        
+        let harmonics: Vec<f32> = if self.pluck_position > 0.0 {
+            // Comb response of the pluck point: harmonic n has weight |sin(n*pi*p)|,
+            // relative to the fundamental and capped at 1 so it only ever removes energy
+            let fundamental_weight = (PI * self.pluck_position).sin();
+            harmonics
+                .iter()
+                .enumerate()
+                .map(|(i, &amp)| {
+                    let n = (i + 2) as f32;
+                    let weight = (n * PI * self.pluck_position).sin().abs() / fundamental_weight;
+                    amp * weight.min(1.0)
+                })
+                .collect()
+        } else {
+            harmonics
+        };
+        
+        // Each successive partial is scaled by rolloff once more: softer plucks are
+        // darker, and palm muting halves every step so the upper harmonics all but vanish
+        let mut rolloff = 0.5 + 0.5 * note.velocity;
+        if note.palm_mute {
+            rolloff *= 0.5;
+        }
+        if rolloff < 1.0 {
+            harmonics.iter().enumerate().map(|(i, &amp)| amp * rolloff.powi(i as i32 + 1)).collect()
+        } else {
+            harmonics
+        }
+    }
+    
+    fn vibrato_ratio(&self) -> f32 {
+        if self.vibrato_depth == 0.0 {
+            return 1.0;
+        }
+        let cents = self.vibrato_depth * (self.vibrato_phase * 2.0 * PI).sin();
+        2.0_f32.powf(cents / 1200.0)
+    }
+    
+    fn tremolo_gain(&self) -> f32 {
+        if self.tremolo_depth == 0.0 {
+            return 1.0;
+        }
+        let lfo = 0.5 + 0.5 * (self.tremolo_phase * 2.0 * PI).cos();
+        1.0 - self.tremolo_depth * (1.0 - lfo)
+    }
+    
+    fn pitch_bend_ratio(&self) -> f32 {
+        if self.pitch_bend == 0.0 && self.whammy == 0.0 {
+            return 1.0;
+        }
+        let semitones = (self.pitch_bend + self.whammy).clamp(-MAX_PITCH_BEND, MAX_PITCH_BEND);
+        2.0_f32.powf(semitones / 12.0)
+    }
+    
+    fn apply_tone(&mut self, channel: usize, x: f32) -> f32 {
+        let state = &mut self.tone_state[channel];
+        if self.tone_coefficient >= 1.0 {
+            *state = x;
+            return x;
+        }
+        *state = flush_denormal(*state + self.tone_coefficient * (x - *state));
+        *state
+    }
+    
+    // The three EQ bands in series, each a transposed direct form II biquad
+    fn apply_eq(&mut self, channel: usize, x: f32) -> f32 {
+        if !self.eq_enabled {
+            return x;
+        }
+        
+        let mut output = x;
+        for (band, state) in self.eq_state[channel].iter_mut().enumerate() {
+            let [b0, b1, b2, a1, a2] = self.eq_coefficients[band];
+            let input = output;
+            output = b0 * input + state[0];
+            state[0] = flush_denormal(b1 * input - a1 * output + state[1]);
+            state[1] = flush_denormal(b2 * input - a2 * output);
+        }
+        output
+    }
+    
+    // Dry signal plus a parallel bank of body-mode resonators
+    fn apply_body_resonance(&mut self, channel: usize, x: f32) -> f32 {
+        if !self.body_resonance_enabled {
+            return x;
+        }
+        
+        let mut output = x;
+        for (mode, &(_, _, gain)) in BODY_MODES.iter().enumerate() {
+            let [b0, b1, b2, a1, a2] = self.body_coefficients[mode];
+            let state = &mut self.body_state[channel][mode];
+            let y = b0 * x + state[0];
+            state[0] = flush_denormal(b1 * x - a1 * y + state[1]);
+            state[1] = flush_denormal(b2 * x - a2 * y);
+            output += gain * y;
+        }
+        output
+    }
+    
+    // Topology-preserving-transform state-variable filter, which stays stable while the
+    // center moves every sample. Band output is normalized to unity gain at the center.
+    fn apply_wah(&mut self, channel: usize, x: f32) -> f32 {
+        if self.wah_mix == 0.0 {
+            return x;
+        }
+        
+        let mut center = self.wah_frequency;
+        if self.envelope_wah_sensitivity > 0.0 {
+            let level = x.abs();
+            let envelope = &mut self.envelope_wah_level[channel];
+            let smoothing = if level > *envelope { self.envelope_wah_attack } else { self.envelope_wah_release };
+            *envelope = flush_denormal(level + smoothing * (*envelope - level));
+            let sweep = (*envelope * self.envelope_wah_sensitivity).min(1.0);
+            center = self.envelope_wah_base * 2.0_f32.powf(self.envelope_wah_range * sweep);
+        }
+        if self.auto_wah_depth > 0.0 {
+            center *= 2.0_f32.powf(self.auto_wah_depth * (self.auto_wah_phase * 2.0 * PI).sin());
+        }
+        let g = (PI * center.min(self.sample_rate * 0.45) / self.sample_rate).tan();
+        let k = 1.0 / self.wah_resonance;
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        
+        let state = &mut self.wah_state[channel];
+        let v3 = x - state[1];
+        let v1 = a1 * state[0] + a2 * v3;
+        let v2 = state[1] + a2 * state[0] + a3 * v3;
+        state[0] = flush_denormal(2.0 * v1 - state[0]);
+        state[1] = flush_denormal(2.0 * v2 - state[1]);
+        
+        x * (1.0 - self.wah_mix) + k * v1 * self.wah_mix
+    }
+    
+    fn apply_compressor(&mut self, channel: usize, x: f32) -> f32 {
+        if self.compressor_ratio == 1.0 {
+            return x;
+        }
+        
+        // Peak envelope follower: fast when the level rises, slow when it falls
+        let level = x.abs();
+        let envelope = &mut self.compressor_envelope[channel];
+        let smoothing = if level > *envelope { self.compressor_attack } else { self.compressor_release };
+        *envelope = flush_denormal(level + smoothing * (*envelope - level));
+        
+        if *envelope <= self.compressor_threshold {
+            return x;
+        }
+        x * (self.compressor_threshold / *envelope).powf(1.0 - 1.0 / self.compressor_ratio)
+    }
+    
+    fn apply_chorus(&mut self, channel: usize, x: f32) -> f32 {
+        if self.chorus_mix == 0.0 {
+            return x;
+        }
+        
+        let buffer = &mut self.chorus_buffers[channel];
+        buffer[self.chorus_position] = x;
+        
+        // Linear interpolation between the two samples around the swept delay
+        let sweep = 0.5 + 0.5 * ((self.chorus_phase + 0.25 * channel as f32) * 2.0 * PI).sin();
+        let delay = (CHORUS_BASE_DELAY + self.chorus_depth * sweep) * self.sample_rate;
+        let read = (self.chorus_position + buffer.len()) as f32 - delay;
+        let index = read.floor() as usize;
+        let fraction = read - read.floor();
+        let a = buffer[index % buffer.len()];
+        let b = buffer[(index + 1) % buffer.len()];
+        let wet = a + (b - a) * fraction;
+        
+        x * (1.0 - self.chorus_mix) + wet * self.chorus_mix
+    }
+    
+    fn apply_reverb(&mut self, x: f32) -> f32 {
+        if self.reverb_mix == 0.0 {
+            return x;
+        }
+        
+        let wet = self.reverb_tank(0, x * REVERB_INPUT_GAIN);
+        x * (1.0 - self.reverb_mix) + wet * self.reverb_mix
+    }
+    
+    // Both tanks are fed the same mono sum; width cross-mixes their outputs, so at 0.0
+    // the tail sits in the center and at 1.0 each side hears only its own tank
+    fn apply_reverb_stereo(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.reverb_mix == 0.0 {
+            return (left, right);
+        }
+        
+        let input = (left + right) * 0.5 * REVERB_INPUT_GAIN;
+        let left_wet = self.reverb_tank(0, input);
+        let right_wet = self.reverb_tank(1, input);
+        let direct = 0.5 + 0.5 * self.reverb_width;
+        let cross = 0.5 - 0.5 * self.reverb_width;
+        let dry = 1.0 - self.reverb_mix;
+        (
+            left * dry + (left_wet * direct + right_wet * cross) * self.reverb_mix,
+            right * dry + (right_wet * direct + left_wet * cross) * self.reverb_mix,
+        )
+    }
+    
+    fn reverb_tank(&mut self, channel: usize, input: f32) -> f32 {
+        if self.high_precision {
+            let (feedback, damping) = (f64::from(self.reverb_feedback), f64::from(self.reverb_damping));
+            let mut output = 0.0;
+            for comb in self.reverb_combs[channel].iter_mut() {
+                output += comb.process_wide(f64::from(input), feedback, damping);
+            }
+            for allpass in self.reverb_allpasses[channel].iter_mut() {
+                output = allpass.process_wide(output);
+            }
+            return output as f32;
+        }
+        
+        let (feedback, damping) = (self.reverb_feedback, self.reverb_damping);
+        let mut output = 0.0;
+        for comb in self.reverb_combs[channel].iter_mut() {
+            output += comb.process(input, feedback, damping);
+        }
+        for allpass in self.reverb_allpasses[channel].iter_mut() {
+            output = allpass.process(output);
+        }
+        output
+    }
+    
+    fn apply_haas(&mut self, x: f32) -> f32 {
+        if self.haas_buffer.is_empty() {
+            return x;
+        }
+        
+        let delayed = std::mem::replace(&mut self.haas_buffer[self.haas_position], x);
+        self.haas_position = (self.haas_position + 1) % self.haas_buffer.len();
+        delayed
+    }
+    
+    // Both channels share one gate, keyed on the louder of them
+    fn apply_noise_gate(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let gain = self.noise_gate_gain(left.abs().max(right.abs()));
+        (left * gain, right * gain)
+    }
+    
+    // Advance the noise gate by one frame whose loudest channel is at level, returning
+    // the gain for that frame
+    fn noise_gate_gain(&mut self, level: f32) -> f32 {
+        if self.noise_gate_threshold <= 0.0 {
+            return 1.0;
+        }
+        
+        if level >= self.noise_gate_threshold {
+            self.noise_gate_hold = NOISE_GATE_HOLD;
+            self.noise_gate_gain = 1.0;
+        } else if self.noise_gate_hold > 0.0 {
+            self.noise_gate_hold -= 1.0 / self.sample_rate;
+        } else {
+            let step = 1.0 / (self.noise_gate_release * self.sample_rate);
+            self.noise_gate_gain = (self.noise_gate_gain - step).max(0.0);
+        }
+        self.noise_gate_gain
+    }
+    
+    // Record the frame into the loop and add the loop's playback to it. process passes
+    // its mono sample as both channels, so a loop plays back the same in either mode.
+    fn apply_looper(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if !self.loop_recording && !self.loop_playing {
+            return (left, right);
+        }
+        
+        if self.loop_length == 0 {
+            // First pass: the loop grows until recording stops or it fills up
+            if self.loop_recording {
+                self.loop_buffers[0].push(left);
+                self.loop_buffers[1].push(right);
+                if self.loop_buffers[0].len() >= self.loop_buffers[0].capacity() {
+                    self.stop_loop_recording();
+                }
+            }
+            return (left, right);
+        }
+        
+        let position = self.loop_position;
+        let played = (self.loop_buffers[0][position], self.loop_buffers[1][position]);
+        if self.loop_recording {
+            self.loop_buffers[0][position] += left;
+            self.loop_buffers[1][position] += right;
+        }
+        self.loop_position = (position + 1) % self.loop_length;
+        if self.loop_playing {
+            (left + played.0, right + played.1)
+        } else {
+            (left, right)
+        }
+    }
+    
+    fn apply_delay(&mut self, channel: usize, x: f32) -> f32 {
+        if self.delay_mix == 0.0 {
+            return x;
+        }
+        
+        if self.high_precision {
+            let buffer = &mut self.delay_wide_buffers[channel];
+            let delayed = buffer[self.delay_position];
+            buffer[self.delay_position] = flush_denormal_wide(f64::from(x) + delayed * f64::from(self.delay_feedback));
+            let mix = f64::from(self.delay_mix);
+            return (f64::from(x) * (1.0 - mix) + delayed * mix) as f32;
+        }
+        
+        let buffer = &mut self.delay_buffers[channel];
+        let delayed = buffer[self.delay_position];
+        buffer[self.delay_position] = flush_denormal(x + delayed * self.delay_feedback);
+        x * (1.0 - self.delay_mix) + delayed * self.delay_mix
+    }
+    
+    fn metronome_click(&self, time: f32) -> f32 {
+        let beat_length = 60.0 / self.metronome_bpm;
+        let elapsed = time - self.metronome_start;
+        let beat = (elapsed / beat_length).floor();
+        let position = elapsed - beat * beat_length;
+        if position >= CLICK_DURATION {
+            return 0.0;
+        }
+        
+        let accent = (beat as u32).is_multiple_of(self.metronome_beats_per_bar);
+        let frequency = if accent { ACCENT_CLICK_FREQUENCY } else { CLICK_FREQUENCY };
+        let level = if accent { 1.0 } else { 0.6 };
+        self.metronome_volume * level * (-position / CLICK_DECAY).exp() * (2.0 * PI * frequency * position).sin()
+    }
+    
+    // First-order DC blocker: y[n] = x[n] - x[n-1] + R * y[n-1]
+    fn apply_dc_block(&mut self, channel: usize, x: f32) -> f32 {
+        if !self.dc_block_enabled {
+            return x;
+        }
+        
+        let coefficient = 1.0 - 2.0 * PI * DC_BLOCK_CUTOFF / self.sample_rate;
+        let y = flush_denormal(x - self.dc_block_input[channel] + coefficient * self.dc_block_output[channel]);
+        self.dc_block_input[channel] = x;
+        self.dc_block_output[channel] = y;
+        y
+    }
+    
+    // process_multi output for a string; free voices go to the first
+    fn string_channel(&self, string_index: usize, channel_count: usize) -> usize {
+        self.string_channels.get(string_index).map_or(0, |channel| channel % channel_count)
+    }
+    
+    // Whether set_string_muted and set_string_solo let a string be heard
+    fn string_audible(&self, string_index: usize) -> bool {
+        if self.string_muted.get(string_index) == Some(&true) {
+            return false;
+        }
+        !self.string_soloed.contains(&true) || self.string_soloed.get(string_index) == Some(&true)
+    }
+    
+    // Equal-power pan law
+    fn pan_gains(&self, note: &GuitarNote) -> (f32, f32) {
+        let pan = self.string_pans.get(note.string_index).copied().unwrap_or(0.0);
+        let pan = pan * (1.0 - self.dynamic_pan * note.velocity);
+        let angle = (pan + 1.0) * PI / 4.0;
+        (angle.cos(), angle.sin())
+    }
+    
+    // The soft clipper at the sample rate (see saturate)
+    fn soft_clip(&self, x: f32) -> f32 {
+        saturate(x, self.drive)
+    }
+    
+    fn apply_soft_clip(&mut self, channel: usize, x: f32) -> f32 {
+        if self.oversampling == 1 {
+            return self.soft_clip(x);
+        }
+        self.clip_oversamplers[channel].process(x, &self.oversampling_kernel, self.drive)
+    }
+}
+
+// Rust-only API (signatures wasm-bindgen can't export)
+impl GuitarSynthesizer {
+    // Fretboard position that best produces freq with the current tuning and capo, as
+    // (string_index, fret). Lower frets win when several strings can play the pitch;
+    // None when no position on the neck is within FRET_POSITION_TOLERANCE_CENTS.
+    pub fn fret_position_for_frequency(&self, freq: f32) -> Option<(usize, i32)> {
+        self.fret_position_among(freq, |_| true)
+    }
+    
+    // The envelope a new synthesizer starts with, as set_envelope's (attack, decay,
+    // sustain, release), for a UI's reset button
+    pub fn default_envelope() -> (f32, f32, f32, f32) {
+        DEFAULT_ENVELOPE
+    }
+    
+    // Render into any number of output channels, each string into the one set_string_channel
+    // picked. One channel renders like process and two like process_stereo, panning
+    // included. With more, each channel runs its own copy of the effects chain, but the
+    // stereo bus effects (reverb, set_stereo_width and the looper) are left out. The
+    // per-channel state is allocated the first time a call needs more channels.
+    pub fn process_multi(&mut self, channels: &mut [&mut [f32]]) -> Result<(), String> {
+        let frames = channels.first().map_or(0, |channel| channel.len());
+        if let Some(index) = channels.iter().position(|channel| channel.len() != frames) {
+            return Err(format!("channel {} has {} frames, channel 0 has {}", index, channels[index].len(), frames));
+        }
+        
+        match channels {
+            [] => {}
+            [mono] => self.process(mono),
+            [left, right] => self.process_stereo(left, right),
+            _ => self.process_channels(channels),
+        }
+        Ok(())
+    }
+}
+
+impl GuitarNote {
+    // One step of the Karplus-Strong loop: read the oldest sample, feed back the damped
+    // two-point average through the tuning allpass.
+    fn next_string_sample(&mut self, damping: f32) -> f32 {
+        let length = self.delay_line.len();
+        if length == 0 {
+            return 0.0;
+        }
+        
+        let current = self.delay_line[self.delay_index];
+        let next = self.delay_line[(self.delay_index + 1) % length];
+        let mut averaged = flush_denormal(0.5 * (current + next) * damping);
+        if self.loop_lowpass > 0.0 {
+            let smoothed = averaged + self.loop_lowpass * (self.loop_lowpass_state - averaged);
+            self.loop_lowpass_state = flush_denormal(smoothed);
+            averaged = self.loop_lowpass_state;
+        }
+        
+        let tuned = flush_denormal(self.allpass_coefficient * averaged + self.allpass_input
+            - self.allpass_coefficient * self.allpass_output);
+        self.allpass_input = averaged;
+        self.allpass_output = tuned;
+        
+        self.delay_line[self.delay_index] = tuned;
+        self.delay_index = (self.delay_index + 1) % length;
+        current
+    }
+    
+    // Follow a pitch change on a ringing string. The fractional part only moves the
+    // allpass; when the whole-sample length changes the loop contents are resampled
+    // so the string keeps vibrating instead of being re-plucked. A loop that would
+    // outgrow the room reserved for it (see LOOP_HEADROOM) stays at its last pitch
+    // rather than allocate in the audio callback.
+    fn retune_string(&mut self, sample_rate: f32, frequency: f32) {
+        let old_length = self.delay_line.len();
+        if old_length == 0 || frequency == self.loop_frequency || frequency <= 0.0 || !frequency.is_finite() {
+            return;
+        }
+        
+        self.loop_frequency = frequency;
+        let (length, allpass_coefficient) = string_loop_tuning(sample_rate, frequency, self.loop_lowpass);
+        if length > self.resample_buffer.capacity() {
+            return;
+        }
+        self.allpass_coefficient = allpass_coefficient;
+        if length == old_length {
+            return;
+        }
+        
+        let step = old_length as f32 / length as f32;
+        let mut resampled = std::mem::take(&mut self.resample_buffer);
+        resampled.clear();
+        resampled.extend((0..length).map(|k| {
+            let position = k as f32 * step;
+            let index = position as usize;
+            let fraction = position - index as f32;
+            let a = self.delay_line[(self.delay_index + index) % old_length];
+            let b = self.delay_line[(self.delay_index + index + 1) % old_length];
+            a + (b - a) * fraction
+        }));
+        self.resample_buffer = std::mem::replace(&mut self.delay_line, resampled);
+        self.delay_index = 0;
+    }
+    
+    // After a sample-rate change, resample the string loop to the length that keeps its
+    // pitch, and make room for the pitch wheel at the new rate
+    fn rescale_string(&mut self, sample_rate: f32) {
+        if self.delay_line.is_empty() {
+            return;
+        }
+        let frequency = self.loop_frequency;
+        let (length, _) = string_loop_tuning(sample_rate, frequency, self.loop_lowpass);
+        self.resample_buffer.reserve(length.saturating_sub(self.resample_buffer.len()));
+        self.loop_frequency = 0.0;
+        self.retune_string(sample_rate, frequency);
+        
+        let capacity = self.delay_line.len() * LOOP_HEADROOM;
+        self.delay_line.reserve(capacity.saturating_sub(self.delay_line.len()));
+        self.resample_buffer.reserve(capacity.saturating_sub(self.resample_buffer.len()));
+    }
+    
+    fn envelope(&self, elapsed: f32) -> f32 {
+        let release_start = self.duration - self.release_time;
+        if elapsed < release_start {
+            self.attack_decay_level(elapsed)
+        } else {
+            // Release phase, ramping down from wherever attack/decay/sustain had reached
+            let release_elapsed = elapsed - release_start;
+            let level = self.fade_level.unwrap_or_else(|| self.attack_decay_level(release_start));
+            level * (1.0 - self.ramp((release_elapsed / self.release_time).min(1.0)))
+        }
+    }
+    
+    // How far along its ramp (0-1) an attack or release is at progress (0-1), per the
+    // note's envelope curve
+    fn ramp(&self, progress: f32) -> f32 {
+        let k = ENVELOPE_CURVE_STEEPNESS;
+        match self.envelope_curve {
+            EnvelopeCurve::Linear => progress,
+            EnvelopeCurve::Exponential => (1.0 - (-k * progress).exp()) / (1.0 - (-k).exp()),
+            EnvelopeCurve::Logarithmic => ((k * progress).exp() - 1.0) / (k.exp() - 1.0),
+        }
+    }
+    
+    fn envelope_stage(&self, elapsed: f32) -> &'static str {
+        if elapsed >= self.duration - self.release_time {
+            "release"
+        } else if elapsed < self.attack_time {
+            "attack"
+        } else if elapsed < self.attack_time + self.decay_time {
+            "decay"
+        } else {
+            "sustain"
+        }
+    }
+    
+    fn attack_decay_level(&self, elapsed: f32) -> f32 {
+        if elapsed < self.attack_time {
+            // Attack phase
+            self.ramp(elapsed / self.attack_time)
+        } else if elapsed < self.attack_time + self.decay_time {
+            // Decay phase
+            let decay_progress = (elapsed - self.attack_time) / self.decay_time;
+            1.0 - (1.0 - self.pressed_sustain_level()) * decay_progress
+        } else {
+            // Sustain phase
+            self.pressed_sustain_level()
+        }
+    }
+    
+    // Sustain level raised by the note's pressure (see PRESSURE_LEVEL)
+    fn pressed_sustain_level(&self) -> f32 {
+        self.sustain_level * (1.0 + PRESSURE_LEVEL * self.pressure)
+    }
+    
+    // Total length in seconds, release included. The release is shortened to fit, but
+    // never into the attack, which would leave it nothing to ramp down from.
+    fn set_length(&mut self, duration: f32) {
+        self.duration = duration;
+        self.release_time = self.release_time.min((duration - self.attack_time).max(duration * 0.5));
+    }
+    
+    // Enter the release phase now, unless it's already under way
+    fn release(&mut self, elapsed: f32) {
+        if elapsed < self.duration - self.release_time {
+            self.duration = elapsed + self.release_time;
+        }
+    }
+    
+    // Shorten the note to end fade seconds after elapsed, ramping down from its current
+    // level along the envelope curve, even mid-release. Notes already due to end sooner
+    // are left alone.
+    fn fade_out(&mut self, elapsed: f32, fade: f32) {
+        if elapsed + fade >= self.duration {
+            return;
+        }
+        
+        self.fade_level = Some(self.envelope(elapsed));
+        self.release_time = fade;
+        self.duration = elapsed + fade;
+    }
+    
+    // Advance a slide by one sample; true when it crosses a fret. The last fret lands
+    // exactly on the target pitch.
+    fn update_slide(&mut self, sample_rate: f32) -> bool {
+        if self.slide_frets_left == 0 {
+            return false;
+        }
+        
+        self.slide_clock += 1.0 / sample_rate;
+        if self.slide_clock < self.slide_fret_time {
+            return false;
+        }
+        self.slide_clock -= self.slide_fret_time;
+        self.slide_frets_left -= 1;
+        self.frequency = if self.slide_frets_left == 0 {
+            self.bend_target
+        } else {
+            self.frequency * self.slide_ratio
+        };
+        true
+    }
+    
+    // Glide the pressure one sample toward its target and brighten signal by it; the
+    // level swell comes through the envelope's sustain
+    fn apply_pressure(&mut self, signal: f32, step: f32) -> f32 {
+        self.pressure += (self.pressure_target - self.pressure) * step;
+        if (self.pressure_target - self.pressure).abs() < 1e-4 {
+            self.pressure = self.pressure_target;
+        }
+        let tilt = signal - self.pressure_previous;
+        self.pressure_previous = signal;
+        signal + PRESSURE_BRIGHTNESS * self.pressure * tilt
+    }
+    
+    // Advance an in-progress bend by one sample
+    fn update_bend(&mut self, sample_rate: f32) {
+        if self.bend_rate == 0.0 {
+            return;
+        }
+        
+        self.frequency += self.bend_rate / sample_rate;
+        let reached = if self.bend_rate > 0.0 {
+            self.frequency >= self.bend_target
+        } else {
+            self.frequency <= self.bend_target
+        };
+        if reached {
+            self.frequency = self.bend_target;
+            self.bend_rate = 0.0;
+        }
+    }
+}
+
+// AudioWorklet processor implementation
+#[wasm_bindgen]
+pub struct GuitarAudioProcessor {
+    synthesizer: GuitarSynthesizer,
+}
+
+#[wasm_bindgen]
+impl GuitarAudioProcessor {
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> Self {
+        GuitarAudioProcessor {
+            synthesizer: GuitarSynthesizer::new(sample_rate),
+        }
+    }
+    
+    pub fn new_with_strings(sample_rate: f32, open_frequencies: &[f32]) -> Self {
+        GuitarAudioProcessor {
+            synthesizer: GuitarSynthesizer::new_with_strings(sample_rate, open_frequencies),
+        }
+    }
+    
+    pub fn new_twelve_string(sample_rate: f32) -> Self {
+        GuitarAudioProcessor {
+            synthesizer: GuitarSynthesizer::new_twelve_string(sample_rate),
+        }
+    }
+    
+    pub fn process_audio(&mut self, output: &mut [f32]) {
+        self.synthesizer.process(output);
+    }
+    
+    pub fn process_audio_stereo(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.synthesizer.process_stereo(left, right);
+    }
+    
+    pub fn render(&mut self, num_samples: usize) -> Vec<f32> {
+        self.synthesizer.render(num_samples)
+    }
+    
+    pub fn peek(&self, num_samples: usize) -> Vec<f32> {
+        self.synthesizer.peek(num_samples)
+    }
+    
+    pub fn string_count(&self) -> usize {
+        self.synthesizer.string_count()
+    }
+    
+    pub fn open_frequency(&self, string_index: usize) -> f32 {
+        self.synthesizer.open_frequency(string_index)
+    }
+    
+    pub fn get_frequency(&self, string_index: usize, fret: i32) -> f32 {
+        self.synthesizer.get_frequency(string_index, fret)
+    }
+    
+    pub fn get_note_name(&self, string_index: usize, fret: i32) -> String {
+        self.synthesizer.get_note_name(string_index, fret)
+    }
+    
+    pub fn identify_chord(&self, fret_positions: &[i32]) -> Option<String> {
+        self.synthesizer.identify_chord(fret_positions)
+    }
+    
+    pub fn chord_voicing(&self, root: &str, quality: &str, position: i32) -> Vec<i32> {
+        self.synthesizer.chord_voicing(root, quality, position)
+    }
+    
+    pub fn spectrum(&self, fft_size: usize) -> Vec<f32> {
+        self.synthesizer.spectrum(fft_size)
+    }
+    
+    pub fn detect_pitch(&self) -> Option<f32> {
+        self.synthesizer.detect_pitch()
+    }
+    
+    pub fn render_to_wav(&mut self, num_samples: usize) -> Vec<u8> {
+        self.synthesizer.render_to_wav(num_samples)
+    }
+    
+    pub fn play_chord(&mut self, fret_positions: &[i32]) {
+        self.synthesizer.play_chord(fret_positions);
+    }
+    
+    pub fn strum(&mut self, fret_positions: &[i32], direction: i32, duration_ms: f32) {
+        self.synthesizer.strum(fret_positions, direction, duration_ms);
+    }
+    
+    pub fn play_note(&mut self, string_index: usize, fret: i32) {
+        self.synthesizer.play_note(string_index, fret);
+    }
+    
+    pub fn play_note_with_duration(&mut self, string_index: usize, fret: i32, duration: f32) {
+        self.synthesizer.play_note_with_duration(string_index, fret, duration);
+    }
+    
+    pub fn try_play_note(&mut self, string_index: usize, fret: i32) -> Result<(), String> {
+        self.synthesizer.try_play_note(string_index, fret)
+    }
+    
+    pub fn play_note_with_velocity(&mut self, string_index: usize, fret: i32, velocity: f32) {
+        self.synthesizer.play_note_with_velocity(string_index, fret, velocity);
+    }
+    
+    pub fn play_note_by_name(&mut self, note: &str, octave: i32) {
+        self.synthesizer.play_note_by_name(note, octave);
+    }
+    
+    pub fn play_frequency(&mut self, freq: f32, duration: f32) {
+        self.synthesizer.play_frequency(freq, duration);
+    }
+    
+    pub fn play_note_swell(&mut self, string_index: usize, fret: i32, swell_time: f32) {
+        self.synthesizer.play_note_swell(string_index, fret, swell_time);
+    }
+    
+    pub fn play_note_muted(&mut self, string_index: usize, fret: i32) {
+        self.synthesizer.play_note_muted(string_index, fret);
+    }
+    
+    pub fn play_harmonic(&mut self, string_index: usize, node_fret: i32) {
+        self.synthesizer.play_harmonic(string_index, node_fret);
+    }
+    
+    pub fn bend_note(&mut self, string_index: usize, semitones: f32, duration: f32) {
+        self.synthesizer.bend_note(string_index, semitones, duration);
+    }
+    
+    pub fn set_note_pressure(&mut self, string_index: usize, pressure: f32) {
+        self.synthesizer.set_note_pressure(string_index, pressure);
+    }
+    
+    pub fn slide_note(&mut self, string_index: usize, target_fret: i32, duration_ms: f32) {
+        self.synthesizer.slide_note(string_index, target_fret, duration_ms);
+    }
+    
+    pub fn hammer_on(&mut self, string_index: usize, new_fret: i32) {
+        self.synthesizer.hammer_on(string_index, new_fret);
+    }
+    
+    pub fn pull_off(&mut self, string_index: usize, new_fret: i32) {
+        self.synthesizer.pull_off(string_index, new_fret);
+    }
+    
+    pub fn release_note(&mut self, string_index: usize) {
+        self.synthesizer.release_note(string_index);
+    }
+    
+    pub fn midi_note_on(&mut self, note: u8, velocity: u8) {
+        self.synthesizer.midi_note_on(note, velocity);
+    }
+    
+    pub fn midi_note_off(&mut self, note: u8) {
+        self.synthesizer.midi_note_off(note);
+    }
+    
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        self.synthesizer.set_sustain_pedal(down);
+    }
+    
+    pub fn schedule_note(&mut self, string_index: usize, fret: i32, start_time: f32, duration: f32) {
+        self.synthesizer.schedule_note(string_index, fret, start_time, duration);
+    }
+    
+    pub fn play_tab(&mut self, tab: &str, tempo_bpm: f32) {
+        self.synthesizer.play_tab(tab, tempo_bpm);
+    }
+    
+    pub fn get_current_time(&self) -> f32 {
+        self.synthesizer.get_current_time()
+    }
+    
+    pub fn reset_time(&mut self) {
+        self.synthesizer.reset_time();
+    }
+    
+    pub fn stop_all(&mut self) {
+        self.synthesizer.stop_all();
+    }
+    
+    pub fn stop_all_smooth(&mut self, fade_ms: f32) {
+        self.synthesizer.stop_all_smooth(fade_ms);
+    }
+    
+    pub fn mute_strings(&mut self, duration_ms: f32) {
+        self.synthesizer.mute_strings(duration_ms);
+    }
+    
+    pub fn set_smooth_retrigger(&mut self, enabled: bool) {
+        self.synthesizer.set_smooth_retrigger(enabled);
+    }
+    
+    pub fn set_retrigger_choke_ms(&mut self, ms: f32) {
+        self.synthesizer.set_retrigger_choke_ms(ms);
+    }
+    
+    pub fn set_legato(&mut self, enabled: bool) {
+        self.synthesizer.set_legato(enabled);
+    }
+    
+    pub fn set_max_voices(&mut self, max: usize) {
+        self.synthesizer.set_max_voices(max);
+    }
+    
+    pub fn active_voice_count(&self) -> usize {
+        self.synthesizer.active_voice_count()
+    }
+    
+    pub fn voice_envelopes(&self) -> Vec<f32> {
+        self.synthesizer.voice_envelopes()
+    }
+    
+    pub fn active_notes_info(&self) -> String {
+        self.synthesizer.active_notes_info()
+    }
+    
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.synthesizer.set_sample_rate(sample_rate);
+    }
+    
+    pub fn set_synthesis_mode(&mut self, mode: u32) {
+        self.synthesizer.set_synthesis_mode(mode);
+    }
+    
+    pub fn set_pluck_strength(&mut self, strength: f32) {
+        self.synthesizer.set_pluck_strength(strength);
+    }
+    
+    pub fn set_string_damping(&mut self, damping: f32) {
+        self.synthesizer.set_string_damping(damping);
+    }
+    
+    pub fn set_string_damping_for(&mut self, string_index: usize, damping: f32) {
+        self.synthesizer.set_string_damping_for(string_index, damping);
+    }
+    
+    pub fn set_tuning(&mut self, frequencies: &[f32]) {
+        self.synthesizer.set_tuning(frequencies);
+    }
+    
+    pub fn set_tuning_from_notes(&mut self, midi_notes: &[i32]) {
+        self.synthesizer.set_tuning_from_notes(midi_notes);
+    }
+    
+    pub fn transpose(&mut self, semitones: i32) {
+        self.synthesizer.transpose(semitones);
+    }
+    
+    pub fn set_concert_pitch(&mut self, a4_hz: f32) {
+        self.synthesizer.set_concert_pitch(a4_hz);
+    }
+    
+    // [attack, decay, sustain, release], since wasm-bindgen can't return a tuple
+    pub fn default_envelope() -> Vec<f32> {
+        let (attack, decay, sustain, release) = GuitarSynthesizer::default_envelope();
+        vec![attack, decay, sustain, release]
+    }
+    
+    pub fn set_envelope(&mut self, attack: f32, decay: f32, sustain: f32, release: f32) {
+        self.synthesizer.set_envelope(attack, decay, sustain, release);
+    }
+    
+    pub fn set_envelope_curve(&mut self, curve: u32) {
+        self.synthesizer.set_envelope_curve(curve);
+    }
+    
+    pub fn set_velocity_attack(&mut self, enabled: bool) {
+        self.synthesizer.set_velocity_attack(enabled);
+    }
+    
+    pub fn set_tone(&mut self, cutoff_hz: f32) {
+        self.synthesizer.set_tone(cutoff_hz);
+    }
+    
+    pub fn set_eq(&mut self, low_gain: f32, mid_gain: f32, high_gain: f32) {
+        self.synthesizer.set_eq(low_gain, mid_gain, high_gain);
+    }
+    
+    pub fn set_vibrato(&mut self, depth_cents: f32, rate_hz: f32) {
+        self.synthesizer.set_vibrato(depth_cents, rate_hz);
+    }
+    
+    pub fn set_pitch_bend(&mut self, semitones: f32) {
+        self.synthesizer.set_pitch_bend(semitones);
+    }
+    
+    pub fn whammy_dive(&mut self, semitones: f32, duration: f32) {
+        self.synthesizer.whammy_dive(semitones, duration);
+    }
+    
+    pub fn set_tremolo(&mut self, rate_hz: f32, depth: f32) {
+        self.synthesizer.set_tremolo(rate_hz, depth);
+    }
+    
+    pub fn set_chorus(&mut self, rate_hz: f32, depth_ms: f32, mix: f32) {
+        self.synthesizer.set_chorus(rate_hz, depth_ms, mix);
+    }
+    
+    pub fn set_reverb(&mut self, room_size: f32, damping: f32, mix: f32, width: f32) {
+        self.synthesizer.set_reverb(room_size, damping, mix, width);
+    }
+    
+    pub fn last_buffer_peak(&self) -> f32 {
+        self.synthesizer.last_buffer_peak()
+    }
+    
+    pub fn last_buffer_rms(&self) -> f32 {
+        self.synthesizer.last_buffer_rms()
+    }
+    
+    pub fn is_silent(&self) -> bool {
+        self.synthesizer.is_silent()
+    }
+    
+    pub fn set_string_pan(&mut self, string_index: usize, pan: f32) {
+        self.synthesizer.set_string_pan(string_index, pan);
+    }
+    
+    pub fn set_dynamic_pan(&mut self, amount: f32) {
+        self.synthesizer.set_dynamic_pan(amount);
+    }
+    
+    pub fn set_string_muted(&mut self, string_index: usize, muted: bool) {
+        self.synthesizer.set_string_muted(string_index, muted);
+    }
+    
+    pub fn set_string_solo(&mut self, string_index: usize, solo: bool) {
+        self.synthesizer.set_string_solo(string_index, solo);
+    }
+    
+    pub fn set_string_channel(&mut self, string_index: usize, channel: usize) {
+        self.synthesizer.set_string_channel(string_index, channel);
+    }
+    
+    pub fn set_pluck_position(&mut self, position: f32) {
+        self.synthesizer.set_pluck_position(position);
+    }
+    
+    pub fn set_brightness_decay(&mut self, amount: f32) {
+        self.synthesizer.set_brightness_decay(amount);
+    }
+    
+    pub fn default_harmonics() -> Vec<f32> {
+        GuitarSynthesizer::default_harmonics()
+    }
+    
+    pub fn set_harmonic_profile(&mut self, amplitudes: &[f32]) {
+        self.synthesizer.set_harmonic_profile(amplitudes);
+    }
+    
+    pub fn set_string_material(&mut self, material: u32) {
+        self.synthesizer.set_string_material(material);
+    }
+    
+    pub fn set_sympathetic_resonance(&mut self, amount: f32) {
+        self.synthesizer.set_sympathetic_resonance(amount);
+    }
+    
+    pub fn set_octave_doubler(&mut self, amount: f32) {
+        self.synthesizer.set_octave_doubler(amount);
+    }
+    
+    pub fn set_unison(&mut self, voices: u32, detune_cents: f32) {
+        self.synthesizer.set_unison(voices, detune_cents);
+    }
+    
+    pub fn set_wah(&mut self, frequency: f32, resonance: f32, mix: f32) {
+        self.synthesizer.set_wah(frequency, resonance, mix);
+    }
+    
+    pub fn set_auto_wah(&mut self, rate_hz: f32, depth_octaves: f32) {
+        self.synthesizer.set_auto_wah(rate_hz, depth_octaves);
+    }
+    
+    pub fn set_envelope_wah(&mut self, sensitivity: f32, base_freq: f32, range: f32) {
+        self.synthesizer.set_envelope_wah(sensitivity, base_freq, range);
+    }
+    
+    pub fn set_compressor(&mut self, threshold: f32, ratio: f32, attack_ms: f32, release_ms: f32) {
+        self.synthesizer.set_compressor(threshold, ratio, attack_ms, release_ms);
+    }
+    
+    pub fn set_noise_gate(&mut self, threshold: f32, release_ms: f32) {
+        self.synthesizer.set_noise_gate(threshold, release_ms);
+    }
+    
+    pub fn set_master_gain(&mut self, gain: f32) {
+        self.synthesizer.set_master_gain(gain);
+    }
+    
+    pub fn set_auto_gain(&mut self, enabled: bool) {
+        self.synthesizer.set_auto_gain(enabled);
+    }
+    
+    pub fn set_fret_buzz(&mut self, amount: f32) {
+        self.synthesizer.set_fret_buzz(amount);
+    }
+    
+    pub fn set_pick_noise(&mut self, amount: f32) {
+        self.synthesizer.set_pick_noise(amount);
+    }
+    
+    pub fn set_drive(&mut self, drive: f32) {
+        self.synthesizer.set_drive(drive);
+    }
+    
+    pub fn set_oversampling(&mut self, factor: u32) {
+        self.synthesizer.set_oversampling(factor);
+    }
+    
+    pub fn set_delay(&mut self, time_seconds: f32, feedback: f32, mix: f32) {
+        self.synthesizer.set_delay(time_seconds, feedback, mix);
+    }
+    
+    pub fn set_high_precision(&mut self, enabled: bool) {
+        self.synthesizer.set_high_precision(enabled);
+    }
+    
+    pub fn set_stereo_width(&mut self, delay_ms: f32) {
+        self.synthesizer.set_stereo_width(delay_ms);
+    }
+    
+    pub fn start_loop_recording(&mut self) {
+        self.synthesizer.start_loop_recording();
+    }
+    
+    pub fn stop_loop_recording(&mut self) {
+        self.synthesizer.stop_loop_recording();
+    }
+    
+    pub fn set_loop_playing(&mut self, playing: bool) {
+        self.synthesizer.set_loop_playing(playing);
+    }
+    
+    pub fn clear_loop(&mut self) {
+        self.synthesizer.clear_loop();
+    }
+    
+    pub fn set_metronome(&mut self, bpm: f32, enabled: bool) {
+        self.synthesizer.set_metronome(bpm, enabled);
+    }
+    
+    pub fn set_metronome_beats_per_bar(&mut self, beats_per_bar: u32) {
+        self.synthesizer.set_metronome_beats_per_bar(beats_per_bar);
+    }
+    
+    pub fn set_metronome_volume(&mut self, volume: f32) {
+        self.synthesizer.set_metronome_volume(volume);
+    }
+    
+    pub fn set_arpeggiator(&mut self, enabled: bool, rate_hz: f32, pattern: &[i32]) {
+        self.synthesizer.set_arpeggiator(enabled, rate_hz, pattern);
+    }
+    
+    pub fn set_body_resonance(&mut self, enabled: bool) {
+        self.synthesizer.set_body_resonance(enabled);
+    }
+    
+    pub fn set_dc_block(&mut self, enabled: bool) {
+        self.synthesizer.set_dc_block(enabled);
+    }
+    
+    pub fn set_seed(&mut self, seed: u64) {
+        self.synthesizer.set_seed(seed);
+    }
+    
+    pub fn set_capo(&mut self, fret: i32) {
+        self.synthesizer.set_capo(fret);
+    }
+    
+    pub fn set_partial_capo(&mut self, fret: i32, strings: &[usize]) {
+        self.synthesizer.set_partial_capo(fret, strings);
+    }
+    
+    pub fn set_fret_count(&mut self, frets: u32) {
+        self.synthesizer.set_fret_count(frets);
+    }
+    
+    pub fn set_intonation_offset(&mut self, fret: i32, cents: f32) {
+        self.synthesizer.set_intonation_offset(fret, cents);
+    }
+    
+    pub fn export_settings(&self) -> String {
+        self.synthesizer.export_settings()
+    }
+    
+    pub fn import_settings(&mut self, json: &str) {
+        self.synthesizer.import_settings(json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SAMPLE_RATE: f32 = 44100.0;
+    
+    fn soft_clip_curve(drive: f32) -> Vec<f32> {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_drive(drive);
+        (-4000..=4000).map(|i| synth.soft_clip(i as f32 * 0.001)).collect()
+    }
+    
+    // (step across the retrigger, largest step while ringing) when replaying a string
+    // that has rung for half a second, timed to land near a peak of the waveform
+    fn retrigger_jump(smooth: bool, curve: u32) -> (f32, f32) {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_envelope_curve(curve);
+        synth.set_smooth_retrigger(smooth);
+        synth.play_note(0, 0);
+        let mut before = synth.render(SAMPLE_RATE as usize / 2);
+        let peak = before[before.len() - 2048..].iter().fold(0.0, |max: f32, x| max.max(x.abs()));
+        while before[before.len() - 1].abs() < 0.8 * peak {
+            before.push(synth.render(1)[0]);
+        }
+        synth.play_note(0, 0);
+        let after = synth.render(1);
+        
+        let ringing_step = before[before.len() - 2048..]
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        ((after[0] - before[before.len() - 1]).abs(), ringing_step)
+    }
+    
+    // Power-weighted mean frequency of a naive DFT
+    fn spectral_centroid(samples: &[f32]) -> f32 {
+        let n = samples.len();
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        for bin in 1..n / 2 {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (i, &x) in samples.iter().enumerate() {
+                let angle = 2.0 * PI * ((bin * i) % n) as f32 / n as f32;
+                re += x * angle.cos();
+                im -= x * angle.sin();
+            }
+            let power = re * re + im * im;
+            weighted += power * bin as f32;
+            total += power;
+        }
+        weighted / total * SAMPLE_RATE / n as f32
+    }
+    
+    // Centroid ratio late / early in an open A string, 50 ms and 600 ms in
+    fn darkening(brightness_decay: f32) -> f32 {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_brightness_decay(brightness_decay);
+        synth.play_note(1, 0);
+        synth.render(2205);
+        let early = spectral_centroid(&synth.render(1024));
+        synth.render(23000);
+        let late = spectral_centroid(&synth.render(1024));
+        late / early
+    }
+    
+    fn render_chord(synth: &mut GuitarSynthesizer) -> Vec<f32> {
+        synth.play_chord(&[0, 2, 2, 1, 0, 0]);
+        synth.render(4096)
+    }
+    
+    #[test]
+    fn renders_are_deterministic_without_seeding() {
+        let first = render_chord(&mut GuitarSynthesizer::new(SAMPLE_RATE));
+        let second = render_chord(&mut GuitarSynthesizer::new(SAMPLE_RATE));
+        assert_eq!(first, second);
+    }
+    
+    #[test]
+    fn same_seed_reproduces_the_same_samples() {
+        let render_seeded = |seed| {
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_seed(seed);
+            render_chord(&mut synth)
+        };
+        assert_eq!(render_seeded(42), render_seeded(42));
+        assert_ne!(render_seeded(42), render_seeded(7));
+    }
+    
+    #[test]
+    fn smooth_retrigger_avoids_the_jump_of_a_hard_restart() {
+        for curve in 0..3 {
+            let (hard, ringing_step) = retrigger_jump(false, curve);
+            let (smooth, _) = retrigger_jump(true, curve);
+            assert!(hard > 2.0 * ringing_step, "curve {curve}: hard restart step {hard} vs {ringing_step}");
+            assert!(smooth <= ringing_step, "curve {curve}: smooth retrigger step {smooth} vs {ringing_step}");
+        }
+    }
+    
+    #[test]
+    fn fading_mid_release_keeps_the_level_under_every_curve() {
+        for curve in 0..3 {
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_envelope(0.001, 0.0, 1.0, 1.0);
+            synth.set_envelope_curve(curve);
+            synth.play_note_with_duration(0, 0, 1.5);
+            synth.render(SAMPLE_RATE as usize);
+            let before = synth.voice_envelopes()[1];
+            
+            synth.stop_all_smooth(200.0);
+            synth.render(1);
+            let after = synth.voice_envelopes()[1];
+            assert!(before > 0.05 && before < 0.95, "curve {curve}: mid-release level {before}");
+            assert!((after - before).abs() < 0.01, "curve {curve}: level {before} -> {after}");
+        }
+    }
+    
+    #[test]
+    fn brightness_decay_darkens_notes_as_they_ring() {
+        let plain = darkening(0.0);
+        let decaying = darkening(1.0);
+        assert!(decaying < 1.0, "centroid ratio {decaying}");
+        assert!(decaying < plain * 0.85, "centroid ratio {decaying} vs {plain} without decay");
+    }
+    
+    #[test]
+    fn decaying_tails_flush_to_zero_instead_of_going_denormal() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_tone(2000.0);
+        synth.set_body_resonance(true);
+        synth.set_compressor(0.1, 4.0, 5.0, 200.0);
+        synth.set_wah(800.0, 4.0, 0.5);
+        synth.set_delay(0.1, 0.5, 0.3);
+        synth.set_reverb(0.8, 0.5, 0.3, 1.0);
+        synth.play_note_with_duration(0, 0, 0.2);
+        
+        // Long after the note and every effect tail have died away
+        let output = synth.render(SAMPLE_RATE as usize * 20);
+        let denormals = output.iter().filter(|x| x.is_subnormal()).count();
+        assert_eq!(denormals, 0, "{denormals} subnormal samples");
+        assert!(output[output.len() - 1000..].iter().all(|&x| x == 0.0));
+    }
+    
+    // Fundamental of a steady tone from the autocorrelation peak, refined with a
+    // parabola through its neighbours. Searches periods of 2 to 20 ms.
+    fn measured_pitch(samples: &[f32], sample_rate: f32) -> f32 {
+        let correlation = |lag: usize| {
+            samples[..samples.len() - lag].iter().zip(&samples[lag..]).map(|(a, b)| a * b).sum::<f32>()
+        };
+        let lags = (sample_rate * 0.002) as usize..(sample_rate * 0.02) as usize;
+        let best = lags.max_by(|&a, &b| correlation(a).total_cmp(&correlation(b))).unwrap();
+        let (before, peak, after) = (correlation(best - 1), correlation(best), correlation(best + 1));
+        let offset = 0.5 * (before - after) / (before - 2.0 * peak + after);
+        sample_rate / (best as f32 + offset)
+    }
+    
+    #[test]
+    fn additive_partials_above_nyquist_are_not_rendered() {
+        // A bright profile puts the partials of the high E's 24th fret (1319 Hz) up to
+        // 28 kHz; a low gain keeps the soft clip from adding distortion products. The
+        // analysis starts after the attack and is short enough that the note still rings.
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_synthesis_mode(1);
+        synth.set_harmonic_profile(&[0.5; 20]);
+        synth.set_string_damping(0.999);
+        synth.set_master_gain(0.01);
+        synth.play_note(5, 24);
+        synth.render(SAMPLE_RATE as usize / 100);
+        synth.render(4096);
+        let spectrum = synth.spectrum(4096);
+        
+        // Aliases would fold back between the harmonics about as loud as them; away from
+        // the harmonics there should only be window leakage
+        let fundamental = synth.calculate_frequency(5, 24);
+        let bin_width = SAMPLE_RATE / 4096.0;
+        let peak = spectrum.iter().cloned().fold(0.0, f32::max);
+        for (bin, &magnitude) in spectrum.iter().enumerate() {
+            let frequency = bin as f32 * bin_width;
+            let distance = (frequency - (frequency / fundamental).round() * fundamental).abs();
+            if distance > 100.0 {
+                assert!(magnitude < 1e-2 * peak, "{magnitude} at {frequency} Hz");
+            }
+        }
+    }
+    
+    #[test]
+    fn oversampling_reduces_soft_clip_aliasing() {
+        // Heavy drive on a 3 kHz sine makes odd harmonics far past Nyquist; folded back,
+        // they land between the harmonics that fit
+        let alias_ratio = |factor: u32| {
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_synthesis_mode(1);
+            synth.set_string_damping(0.999);
+            synth.set_drive(10.0);
+            synth.set_oversampling(factor);
+            synth.play_frequency(3000.0, 1.0);
+            synth.render(4096);
+            let spectrum = synth.spectrum(4096);
+            
+            let bin_width = SAMPLE_RATE / 4096.0;
+            let (mut harmonics, mut aliases) = (0.0, 0.0);
+            for (bin, &magnitude) in spectrum.iter().enumerate().skip(10) {
+                let frequency = bin as f32 * bin_width;
+                if (frequency - (frequency / 3000.0).round() * 3000.0).abs() < 60.0 {
+                    harmonics += magnitude * magnitude;
+                } else {
+                    aliases += magnitude * magnitude;
+                }
+            }
+            aliases / harmonics
+        };
+        assert!(alias_ratio(4) < 0.25 * alias_ratio(1));
+    }
+    
+    #[test]
+    fn noise_gate_silences_signals_below_its_threshold() {
+        // A quiet note never reaches the threshold, so once hold and release have run
+        // out the output is exactly zero
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_master_gain(0.01);
+        synth.set_noise_gate(0.1, 10.0);
+        synth.play_note(1, 0);
+        synth.render((SAMPLE_RATE * (NOISE_GATE_HOLD + 0.01)) as usize + 1);
+        assert!(synth.render(SAMPLE_RATE as usize / 10).iter().all(|&s| s == 0.0));
+        
+        // A loud one passes untouched from the sample that opens the gate
+        let mut gated = GuitarSynthesizer::new(SAMPLE_RATE);
+        gated.set_noise_gate(0.01, 10.0);
+        gated.play_note(1, 0);
+        let mut plain = GuitarSynthesizer::new(SAMPLE_RATE);
+        plain.play_note(1, 0);
+        let (gated, plain) = (gated.render(SAMPLE_RATE as usize / 10), plain.render(SAMPLE_RATE as usize / 10));
+        let open = plain.iter().position(|s| s.abs() >= 0.01).unwrap();
+        assert_eq!(gated[open..], plain[open..]);
+    }
+    
+    #[test]
+    fn non_finite_arguments_leave_the_output_finite() {
+        let mut synth = GuitarSynthesizer::new(f32::NAN);
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            synth.set_pluck_strength(bad);
+            synth.set_string_damping(bad);
+            synth.set_string_damping_for(0, bad);
+            synth.set_envelope(bad, bad, bad, bad);
+            synth.set_tone(bad);
+            synth.set_vibrato(bad, bad);
+            synth.set_tremolo(bad, bad);
+            synth.set_pitch_bend(bad);
+            synth.set_pluck_position(bad);
+            synth.set_compressor(bad, bad, bad, bad);
+            synth.set_master_gain(bad);
+            synth.set_drive(bad);
+            synth.set_delay(bad, bad, bad);
+            synth.set_chorus(bad, bad, bad);
+            synth.set_reverb(bad, bad, bad, bad);
+            synth.set_wah(bad, bad, bad);
+            synth.set_string_pan(0, bad);
+            synth.set_tuning(&[bad; 6]);
+            synth.play_frequency(bad, 1.0);
+            synth.play_note_with_velocity(0, 3, bad);
+            synth.play_note_with_duration(1, 0, bad);
+            synth.play_note(2, 0);
+            synth.bend_note(2, bad, bad);
+            synth.slide_note(2, 5, bad);
+            synth.set_note_pressure(2, bad);
+        }
+        
+        let mut left = vec![0.0; SAMPLE_RATE as usize / 10];
+        let mut right = left.clone();
+        synth.process_stereo(&mut left, &mut right);
+        let mono = synth.render(SAMPLE_RATE as usize / 10);
+        assert!(left.iter().chain(&right).chain(&mono).all(|s| s.is_finite()));
+        assert!(mono.iter().any(|s| s.abs() > 0.01), "the valid note should still sound");
+    }
+    
+    #[test]
+    fn is_silent_waits_for_effect_tails() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        assert!(synth.is_silent());
+        synth.set_delay(0.25, 0.3, 0.5);
+        synth.set_reverb(0.5, 0.5, 0.3, 1.0);
+        synth.play_note_with_duration(0, 5, 0.1);
+        assert!(!synth.is_silent());
+        
+        let mut left = vec![0.0; SAMPLE_RATE as usize / 5];
+        let mut right = left.clone();
+        synth.process_stereo(&mut left, &mut right);
+        assert!(synth.active_voice_count() == 0, "the note should have ended");
+        assert!(!synth.is_silent(), "the delay is still echoing");
+        
+        let mut left = vec![0.0; SAMPLE_RATE as usize * 4];
+        let mut right = left.clone();
+        synth.process_stereo(&mut left, &mut right);
+        let mut left = vec![0.0; 128];
+        let mut right = left.clone();
+        synth.process_stereo(&mut left, &mut right);
+        assert!(synth.is_silent());
+    }
+    
+    #[test]
+    fn bypassed_effect_tails_dont_block_is_silent() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_delay(0.25, 0.9, 0.5);
+        synth.set_reverb(0.9, 0.5, 0.3, 1.0);
+        synth.play_note_with_duration(0, 5, 0.1);
+        synth.render(SAMPLE_RATE as usize / 2);
+        assert!(!synth.is_silent(), "the effects are still ringing");
+        
+        synth.set_delay(0.25, 0.9, 0.0);
+        synth.set_reverb(0.9, 0.5, 0.0, 1.0);
+        synth.render(128);
+        assert!(synth.is_silent());
+    }
+    
+    #[test]
+    fn auto_gain_brings_chords_close_to_single_note_loudness() {
+        let rms = |frets: &[i32], auto_gain: bool| {
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_auto_gain(auto_gain);
+            synth.play_chord(frets);
+            let output = synth.render(SAMPLE_RATE as usize / 2);
+            (output.iter().map(|s| s * s).sum::<f32>() / output.len() as f32).sqrt()
+        };
+        let single = [-1, -1, -1, -1, -1, 0];
+        let chord = [0, 2, 2, 1, 0, 0];
+        assert!(rms(&chord, false) / rms(&single, false) > 3.0);
+        assert!(rms(&chord, true) / rms(&single, true) < 2.0);
+        assert_eq!(rms(&single, true), rms(&single, false));
+    }
+    
+    #[test]
+    fn muted_and_unsoloed_strings_are_not_heard() {
+        let peak = |synth: &mut GuitarSynthesizer| {
+            synth.render(SAMPLE_RATE as usize / 20).iter().fold(0.0, |peak: f32, s| peak.max(s.abs()))
+        };
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_string_muted(0, true);
+        synth.play_note(0, 3);
+        assert_eq!(peak(&mut synth), 0.0);
+        
+        synth.set_string_muted(0, false);
+        synth.set_string_solo(5, true);
+        assert_eq!(peak(&mut synth), 0.0, "only the soloed string should sound");
+        synth.play_note(5, 0);
+        assert!(peak(&mut synth) > 0.01);
+        
+        synth.set_string_solo(5, false);
+        synth.play_note(0, 3);
+        assert!(peak(&mut synth) > 0.01, "clearing the solo should restore every string");
+    }
+    
+    #[test]
+    fn play_chord_replaces_notes_string_by_string() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.play_note(0, 3);
+        synth.play_chord(&[-1, 2, 2, 1, 0, 0]);
+        synth.play_chord(&[-1, 2, 2, 1, 0, 0]);
+        assert_eq!(synth.active_voice_count(), 6, "the skipped low string keeps ringing");
+        
+        synth.play_chord(&[0, 2, 2, 1, 0, 0, 3, 3]);
+        assert_eq!(synth.active_voice_count(), 6, "frets past the last string are ignored");
+    }
+    
+    #[test]
+    fn partial_capo_only_raises_the_covered_strings() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let open: Vec<f32> = (0..6).map(|string| synth.open_frequency(string)).collect();
+        synth.set_partial_capo(2, &[2, 3, 4]);
+        let whole_tone = 2.0_f32.powf(2.0 / 12.0);
+        for (string, &frequency) in open.iter().enumerate() {
+            let expected = if (2..=4).contains(&string) { frequency * whole_tone } else { frequency };
+            assert!((synth.open_frequency(string) - expected).abs() < 1e-3, "string {string}");
+        }
+        assert!(synth.try_play_note(0, 24).is_ok());
+        assert!(synth.try_play_note(3, 23).is_err(), "covered strings lose the frets under the capo");
+        
+        synth.set_partial_capo(0, &[]);
+        assert_eq!(synth.open_frequency(3), open[3]);
+        
+        synth.set_partial_capo(i32::MAX, &[3]);
+        assert!(synth.get_frequency(3, 1).is_finite());
+        assert!(synth.try_play_note(3, 0).is_ok(), "the partial capo should sit on the last fret");
+    }
+    
+    #[test]
+    fn high_precision_tails_match_and_survive_switching() {
+        // A second of delay and reverb tail after the note, at f32 throughout or
+        // switching to f64 and back every quarter second
+        let render = |switching: bool| {
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_delay(0.2, 0.7, 0.5);
+            synth.set_reverb(0.8, 0.3, 0.4, 1.0);
+            synth.play_note_with_duration(1, 3, 0.2);
+            let mut output = synth.render(SAMPLE_RATE as usize / 2);
+            for quarter in 0..4 {
+                synth.set_high_precision(switching && quarter % 2 == 0);
+                output.extend(synth.render(SAMPLE_RATE as usize / 4));
+            }
+            output
+        };
+        let narrow = render(false);
+        let switched = render(true);
+        assert_eq!(narrow.len(), switched.len());
+        let error = narrow.iter().zip(&switched).fold(0.0, |error: f32, (a, b)| error.max((a - b).abs()));
+        assert!(error < 1e-4, "switching precision changed the tail by {error}");
+        assert!(switched[switched.len() - 1000..].iter().any(|s| s.abs() > 1e-4), "the tail should still ring");
+    }
+    
+    #[test]
+    fn oversized_tab_frets_are_skipped() {
+        let tab = "e|-99999999999-3-|\nB|---------------|\nG|---------------|\n\
+                   D|---------------|\nA|---------------|\nE|-------------99999999999|";
+        assert_eq!(parse_tab(tab, 6), vec![(5, 3, 13)]);
+        
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.play_tab(tab, 120.0);
+    }
+    
+    #[test]
+    fn capo_stays_on_the_neck() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let open = synth.open_frequency(0);
+        synth.set_capo(i32::MAX);
+        assert!(synth.get_frequency(0, 1).is_finite());
+        assert!((synth.open_frequency(0) / open - 4.0).abs() < 1e-4, "two octaves up at the 24th fret");
+        assert!(synth.try_play_note(0, 0).is_ok(), "the capo should sit on the last fret");
+        assert!(synth.try_play_note(0, 1).is_err());
+    }
+    
+    #[test]
+    fn midi_chords_get_a_string_per_key() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        for key in [60, 64, 67] {
+            synth.midi_note_on(key, 100);
+        }
+        assert_eq!(synth.active_voice_count(), 3);
+        
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.midi_note_on(52, 100);
+        synth.midi_note_on(53, 100);
+        assert_eq!(synth.active_voice_count(), 2);
+    }
+    
+    #[test]
+    fn strum_ignores_non_finite_durations() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.strum(&[0, 2, 2, 1, 0, 0], 1, f32::INFINITY);
+        synth.strum(&[0, 2, 2, 1, 0, 0], -1, f32::NAN);
+        assert!(synth.is_silent(), "nothing should be left waiting to start");
+        
+        synth.strum(&[0, 2, 2, 1, 0, 0], 1, 1.0e9);
+        synth.render(SAMPLE_RATE as usize * 2);
+        assert_eq!(synth.active_voice_count(), 6);
+        let started = synth.active_notes.iter().all(|n| n.start_time <= synth.current_time);
+        assert!(started, "every string starts within a second");
+    }
+    
+    #[test]
+    fn settings_import_survives_deep_nesting_and_restores_the_tuning() {
+        let nested = "[".repeat(200_000);
+        assert!(json::parse(&nested).is_none());
+        assert!(json::parse(&format!("{}{}", "[".repeat(64), "]".repeat(64))).is_some());
+        assert!(json::parse(&format!("{}{}", "[".repeat(65), "]".repeat(65))).is_none());
+        let escaped = json::parse("{\"caf\\u00e9 \\\"\u{e9}\\\"\": 1}").unwrap();
+        assert_eq!(escaped.get("caf\u{e9} \"\u{e9}\"").and_then(JsonValue::as_f32), Some(1.0));
+        
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_tuning_from_notes(&[38, 45, 50, 55, 59, 64]);
+        synth.import_settings(&format!("{{\"tuning\": {}}}", nested));
+        assert_eq!(synth.open_string_frequencies, OPEN_STRING_FREQUENCIES);
+        
+        synth.set_tuning_from_notes(&[38, 45, 50, 55, 59, 64]);
+        synth.import_settings("{\"capo\": 2}");
+        assert_eq!(synth.open_string_frequencies, OPEN_STRING_FREQUENCIES);
+        assert_eq!(synth.capo, 2);
+        
+        synth.set_drive(5.0);
+        synth.set_envelope(0.5, 0.5, 0.5, 0.5);
+        synth.import_settings("{\"drive\": 1e999, \"envelope\": {\"release\": -1e999}}");
+        assert_eq!(synth.drive, DEFAULT_DRIVE);
+        assert_eq!(synth.release_time, DEFAULT_ENVELOPE.3);
+    }
+    
+    #[test]
+    fn off_pitch_frequencies_have_no_fret_position() {
+        let synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let a2 = synth.get_frequency(1, 0);
+        assert_eq!(synth.fret_position_for_frequency(a2), Some((1, 0)));
+        assert_eq!(synth.fret_position_for_frequency(a2 * 2.0_f32.powf(20.0 / 1200.0)), Some((1, 0)));
+        assert_eq!(synth.fret_position_for_frequency(a2 * 2.0_f32.powf(49.0 / 1200.0)), None);
+    }
+    
+    #[test]
+    fn cached_fret_frequencies_match_the_formula() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_tuning_from_notes(&[38, 45, 50, 55, 59, 64]);
+        synth.set_fret_count(22);
+        synth.set_capo(2);
+        synth.set_partial_capo(4, &[2, 3, 4]);
+        synth.set_intonation_offset(5, 12.0);
+        synth.set_intonation_offset(14, -30.0);
+        synth.transpose(-1);
+        
+        for string_index in 0..synth.string_count() {
+            let capo = synth.string_capo(string_index);
+            for fret in 0..=synth.fret_count - capo {
+                let neck_fret = (fret + capo) as usize;
+                let cents = synth.intonation_offsets[neck_fret];
+                let open = synth.open_string_frequencies[string_index];
+                let expected = open * 2.0_f32.powf(neck_fret as f32 / 12.0 + cents / 1200.0);
+                let cached = synth.get_frequency(string_index, fret);
+                assert!((cached / expected - 1.0).abs() < 1e-5, "string {string_index} fret {fret}: {cached}");
+                
+                synth.play_note(string_index, fret);
+                assert_eq!(synth.active_notes.last().map(|n| n.frequency), Some(cached));
+            }
+        }
+    }
+    
+    #[test]
+    fn huge_bends_keep_the_output_finite() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let mut buffer = vec![0.0; 256];
+        synth.play_note(0, 0);
+        synth.process(&mut buffer);
+        synth.bend_note(0, -1000.0, 0.0);
+        synth.process(&mut buffer);
+        synth.bend_note(0, -1000.0, 0.0);
+        synth.set_pitch_bend(-24.0);
+        for _ in 0..20 {
+            synth.process(&mut buffer);
+            assert!(buffer.iter().all(|s| s.is_finite()));
+        }
+        let capacity = synth.active_notes[0].resample_buffer.capacity();
+        assert!(synth.active_notes[0].delay_line.len() <= capacity);
+    }
+    
+    #[test]
+    fn settings_import_keeps_the_concert_pitch() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_concert_pitch(442.0);
+        let a4 = synth.get_frequency(5, 5);
+        assert!((a4 - 442.0).abs() < 0.01, "A4 at {a4}");
+        synth.import_settings("{}");
+        assert_eq!(synth.get_frequency(5, 5), a4);
+    }
+    
+    #[test]
+    fn transposing_up_and_back_down_restores_the_tuning() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let original = synth.open_string_frequencies.clone();
+        synth.transpose(5);
+        assert!((synth.open_frequency(0) / original[0] - 2.0_f32.powf(5.0 / 12.0)).abs() < 1e-5);
+        synth.transpose(-5);
+        for _ in 0..12 {
+            synth.transpose(1);
+        }
+        synth.transpose(-12);
+        synth.transpose(48);
+        synth.transpose(-48);
+        for (string_index, &frequency) in original.iter().enumerate() {
+            let restored = synth.open_frequency(string_index);
+            assert!((restored / frequency - 1.0).abs() < 1e-5, "string {string_index}: {restored} vs {frequency}");
+        }
+    }
+    
+    #[test]
+    fn note_pressure_raises_the_sustain_but_not_the_attack() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_envelope(0.5, 0.0, 0.5, 1.0);
+        synth.play_note(0, 0);
+        synth.render(SAMPLE_RATE as usize / 10);
+        synth.set_note_pressure(0, 1.0);
+        synth.render(SAMPLE_RATE as usize / 10);
+        let attack = synth.voice_envelopes()[1];
+        assert!((attack - 0.4).abs() < 0.01, "attack level {attack}");
+        
+        synth.render(SAMPLE_RATE as usize / 2);
+        let sustain = synth.voice_envelopes()[1];
+        assert!((sustain - 0.5 * (1.0 + PRESSURE_LEVEL)).abs() < 0.01, "sustain level {sustain}");
+    }
+    
+    #[test]
+    fn render_to_wav_writes_a_16_bit_mono_riff_file() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.play_note(0, 0);
+        let frames = 1000;
+        let wav = synth.render_to_wav(frames);
+        let u16_at = |offset: usize| u16::from_le_bytes([wav[offset], wav[offset + 1]]);
+        let u32_at = |offset: usize| u32::from_le_bytes(wav[offset..offset + 4].try_into().unwrap());
+        
+        assert_eq!(wav.len(), 44 + 2 * frames);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(4) as usize, wav.len() - 8);
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(&wav[12..16], b"fmt ");
+        assert_eq!(u32_at(16), 16);
+        assert_eq!((u16_at(20), u16_at(22)), (1, 1), "PCM, mono");
+        assert_eq!(u32_at(24), SAMPLE_RATE as u32);
+        assert_eq!(u32_at(28), SAMPLE_RATE as u32 * 2, "byte rate");
+        assert_eq!((u16_at(32), u16_at(34)), (2, 16), "block align, bits per sample");
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40) as usize, 2 * frames);
+        assert!((0..frames).any(|frame| u16_at(44 + 2 * frame) != 0), "the note should be in the data");
+    }
+    
+    #[test]
+    fn pitch_is_invariant_to_the_sample_rate() {
+        let cents = |measured: f32, expected: f32| 1200.0 * (measured / expected).log2();
+        for rate in [22050.0, 32000.0, 48000.0, 96000.0] {
+            // Set before playing
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.set_sample_rate(rate);
+            synth.play_note(1, 0);
+            synth.render(rate as usize / 10);
+            let pitch = measured_pitch(&synth.render(rate as usize / 10), rate);
+            assert!(cents(pitch, 110.0).abs() < 5.0, "{pitch} Hz at {rate} Hz");
+            
+            // Changed under a ringing string
+            let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+            synth.play_note(1, 0);
+            synth.render(SAMPLE_RATE as usize / 10);
+            synth.set_sample_rate(rate);
+            synth.render(rate as usize / 20);
+            let pitch = measured_pitch(&synth.render(rate as usize / 10), rate);
+            assert!(cents(pitch, 110.0).abs() < 5.0, "{pitch} Hz after switching to {rate} Hz");
+        }
+    }
+    
+    #[test]
+    fn dc_block_removes_constant_offset() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        let output: Vec<f32> = (0..SAMPLE_RATE as usize).map(|_| synth.apply_dc_block(0, 0.5)).collect();
+        
+        // The step passes through at first, then decays toward zero mean
+        assert!(output[0] > 0.4);
+        let tail = &output[output.len() - 1000..];
+        let tail_mean = tail.iter().sum::<f32>() / tail.len() as f32;
+        assert!(tail_mean.abs() < 0.001, "tail mean {}", tail_mean);
+    }
+    
+    #[test]
+    fn dc_block_can_be_disabled() {
+        let mut synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        synth.set_dc_block(false);
+        assert!((0..1000).all(|_| synth.apply_dc_block(0, 0.5) == 0.5));
+    }
+    
+    #[test]
+    fn soft_clip_is_continuous() {
+        for drive in [1.0, 4.0, 20.0] {
+            let curve = soft_clip_curve(drive);
+            // Slope never exceeds drive, so a 0.001 step can't move further than that
+            let max_step = drive * 0.001 * 1.01;
+            for pair in curve.windows(2) {
+                assert!((pair[1] - pair[0]).abs() <= max_step, "jump at drive {}", drive);
+            }
+        }
+    }
+    
+    #[test]
+    fn soft_clip_is_monotonic_and_bounded() {
+        for drive in [0.1, 1.0, 4.0, 20.0] {
+            let curve = soft_clip_curve(drive);
+            for pair in curve.windows(2) {
+                assert!(pair[1] >= pair[0], "not monotonic at drive {}", drive);
+            }
+            assert!(curve.iter().all(|y| y.abs() <= 2.0 / 3.0));
+        }
+    }
+    
+    #[test]
+    fn soft_clip_matches_cubic_for_small_signals_at_unity_drive() {
+        let synth = GuitarSynthesizer::new(SAMPLE_RATE);
+        for i in -50..=50 {
+            let x = i as f32 * 0.01;
+            let cubic = x - (x * x * x) / 3.0;
+            assert!((synth.soft_clip(x) - cubic).abs() < 0.05, "x = {}", x);
+        }
     }
 }